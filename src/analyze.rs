@@ -1,24 +1,811 @@
 use std::f32::consts::PI;
+use std::time::Instant;
 
+use egui_plot::PlotPoint;
+use rustfft::{Fft, FftPlanner};
+use rustfft::num_complex::Complex32;
+use strum_macros::EnumIter;
 
-#[derive(PartialEq)]
+use crate::input::{Channel, Command};
+use crate::output::SAMPLE_RATE;
+
+
+#[derive(Clone, Copy, PartialEq, EnumIter)]
 pub enum PlotView {
     TimeSeries,
     Spectrum,
-    Window
+    Window,
+    Complex
+}
+
+impl std::fmt::Display for PlotView {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlotView::TimeSeries => write!(f, "Time Series"),
+            PlotView::Spectrum => write!(f, "Spectrum"),
+            PlotView::Window => write!(f, "Window"),
+            PlotView::Complex => write!(f, "Re/Im")
+        }
+    }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq, EnumIter)]
 pub enum TimeSeriesTracking {
     Static,
-    Following
+    Following,
+    ZeroCrossing
+}
+
+impl std::fmt::Display for TimeSeriesTracking {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeSeriesTracking::Static => write!(f, "Static"),
+            TimeSeriesTracking::Following => write!(f, "Following"),
+            TimeSeriesTracking::ZeroCrossing => write!(f, "Zero Crossing")
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, EnumIter)]
+pub enum FrequencyEstimator {
+    #[default]
+    PhaseDiff,
+    PeakBin
+}
+
+impl std::fmt::Display for FrequencyEstimator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrequencyEstimator::PhaseDiff => write!(f, "Phase Diff"),
+            FrequencyEstimator::PeakBin => write!(f, "Peak Bin")
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, EnumIter)]
+pub enum SpectrumScaling {
+    #[default]
+    PeakNormalized,
+    Absolute,
+    PerBin
+}
+
+impl std::fmt::Display for SpectrumScaling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpectrumScaling::PeakNormalized => write!(f, "Peak"),
+            SpectrumScaling::Absolute => write!(f, "Absolute"),
+            SpectrumScaling::PerBin => write!(f, "Per-Bin")
+        }
+    }
+}
+
+impl SpectrumScaling {
+    pub fn max_magnitude(&self) -> f64 {
+        match self {
+            SpectrumScaling::PerBin => 2.0,
+            _ => 1.0
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, EnumIter)]
+pub enum GateMode {
+    #[default]
+    Freeze,
+    Blank
+}
+
+impl std::fmt::Display for GateMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GateMode::Freeze => write!(f, "Freeze"),
+            GateMode::Blank => write!(f, "Blank")
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, EnumIter)]
+pub enum Weighting {
+    #[default]
+    Flat,
+    A,
+    C
+}
+
+impl std::fmt::Display for Weighting {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Weighting::Flat => write!(f, "Flat"),
+            Weighting::A => write!(f, "A"),
+            Weighting::C => write!(f, "C")
+        }
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, EnumIter)]
+pub enum NoiseReference {
+    #[default]
+    None,
+    White,
+    Pink
+}
+
+impl std::fmt::Display for NoiseReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NoiseReference::None => write!(f, "None"),
+            NoiseReference::White => write!(f, "White"),
+            NoiseReference::Pink => write!(f, "Pink")
+        }
+    }
+}
+
+// Ideal noise level in dB relative to 1 kHz: flat for white, -3 dB/octave for pink
+pub fn noise_reference_db(reference: NoiseReference, hz: f64) -> f64 {
+    match reference {
+        NoiseReference::None | NoiseReference::White => 0.0,
+        NoiseReference::Pink => -10.0 * (hz / 1000.0).log10()
+    }
+}
+
+fn weighting_response(weighting: Weighting, hz: f32) -> f32 {
+    let f2 = hz * hz;
+    match weighting {
+        Weighting::Flat =>
+            1.0,
+        Weighting::A =>
+            (12194.0_f32.powi(2) * f2 * f2)
+                / ((f2 + 20.6_f32.powi(2))
+                    * ((f2 + 107.7_f32.powi(2)) * (f2 + 737.9_f32.powi(2))).sqrt()
+                    * (f2 + 12194.0_f32.powi(2))),
+        Weighting::C =>
+            (12194.0_f32.powi(2) * f2)
+                / ((f2 + 20.6_f32.powi(2)) * (f2 + 12194.0_f32.powi(2)))
+    }
+}
+
+pub fn weighting_gain(weighting: Weighting, hz: f32) -> f32 {
+    weighting_response(weighting, hz) / weighting_response(weighting, 1000.0)
+}
+
+#[derive(Clone, Copy, Default)]
+pub enum WindowFunction {
+    #[default]
+    Hann,
+    Kaiser { beta: f32 },
+    Gaussian { sigma: f32 },
+    Custom
+}
+
+impl PartialEq for WindowFunction {
+    fn eq(&self, other: &Self) -> bool {
+        use core::mem::discriminant;
+
+        discriminant(self) == discriminant(other)
+    }
+}
+
+impl std::fmt::Display for WindowFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WindowFunction::Hann => write!(f, "Hann"),
+            WindowFunction::Kaiser { .. } => write!(f, "Kaiser"),
+            WindowFunction::Gaussian { .. } => write!(f, "Gaussian"),
+            WindowFunction::Custom => write!(f, "Custom")
+        }
+    }
+}
+
+impl WindowFunction {
+    pub fn options() -> [WindowFunction; 3] {
+        [
+            WindowFunction::Hann,
+            WindowFunction::Kaiser { beta: 8.6 },
+            WindowFunction::Gaussian { sigma: 0.4 }
+        ]
+    }
+}
+
+// Zeroth order modified Bessel function of the first kind
+fn bessel_i0(x: f32) -> f32 {
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    let mut k = 1.0;
+    while term > 1e-8 * sum {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+pub fn load_window_function<const N: usize>(path: &str, normalize: bool) -> Result<[f32; N], String> {
+    let contents = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let coefficients = contents
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.parse::<f32>().map_err(|_| format!("invalid coefficient: {}", value)))
+        .collect::<Result<Vec<_>, _>>()?;
+    if coefficients.is_empty() {
+        return Err("no coefficients found".to_string());
+    }
+
+    // Linearly resample to the analysis size
+    let mut window = [0.0; N];
+    let scale = (coefficients.len() - 1) as f32 / (N.max(2) - 1) as f32;
+    for (i, w) in window.iter_mut().enumerate() {
+        let position = i as f32 * scale;
+        let index = position.floor() as usize;
+        let frac = position - index as f32;
+        let next = coefficients[(index + 1).min(coefficients.len() - 1)];
+        *w = coefficients[index] + frac * (next - coefficients[index]);
+    }
+
+    if normalize {
+        let peak = window.iter().fold(0.0_f32, |peak, w| peak.max(w.abs()));
+        if peak > 0.0 {
+            for w in window.iter_mut() {
+                *w /= peak;
+            }
+        }
+    }
+
+    Ok(window)
 }
 
-pub fn build_window_function<const N: usize>() -> [f32; N] {
+pub fn build_window_function<const N: usize>(window_function: WindowFunction) -> [f32; N] {
     let mut window = [0.0; N];
-    for i in 0..N {
-        window[i] = 0.5 - 0.5 * ( (2.0 * PI * i as f32) / N as f32 ).cos();
+    for (i, w) in window.iter_mut().enumerate() {
+        // Position across the window in [-1, 1)
+        let x = 2.0 * i as f32 / N as f32 - 1.0;
+        *w = match window_function {
+            WindowFunction::Hann | WindowFunction::Custom =>
+                0.5 - 0.5 * ( (2.0 * PI * i as f32) / N as f32 ).cos(),
+            WindowFunction::Kaiser { beta } =>
+                bessel_i0(beta * (1.0 - x * x).sqrt()) / bessel_i0(beta),
+            WindowFunction::Gaussian { sigma } =>
+                (-0.5 * (x / sigma).powi(2)).exp()
+        };
     }
     window
 }
 
+
+pub const FREQ_EST_HISTORY: usize = 32;
+pub const FREQ_LOCK_THRESHOLD_HZ: f32 = 1.0;
+
+pub const DEFAULT_DB_FLOOR: f64 = -140.0;
+
+pub const CORRELATION_HISTORY: usize = 32;
+pub const MAX_SWEEP_POINTS: usize = 200;
+pub const INVERSION_THRESHOLD: f32 = -0.5;
+pub const MAX_ALIGNMENT_DELAY: usize = 2048;
+pub const MAX_TRIGGER_HOLDOFF_MS: f32 = 500.0;
+
+pub const TIMING_SECTIONS: [&str; 5] = ["FFT", "Smoothing", "Scaling", "Estimate", "Time Series"];
+
+// Adds the time since the last lap to a rolling average (in ms); a no-op while timing is off
+pub fn lap(average: &mut f64, clock: &mut Option<Instant>) {
+    if let Some(last) = clock {
+        let now = Instant::now();
+        let ms = 1000.0 * (now - *last).as_secs_f64();
+        *average += 0.05 * (ms - *average);
+        *last = now;
+    }
+}
+
+pub fn magnitude_to_db(magnitude: f64, floor: f64) -> f64 {
+    let epsilon = 10.0_f64.powf(floor / 20.0);
+    (20.0 * magnitude.max(epsilon).log10()).max(floor)
+}
+
+pub fn follow_envelope(input: &[PlotPoint], output: &mut [PlotPoint], attack_ms: f32, release_ms: f32) {
+    let attack = (-1000.0 / (attack_ms * SAMPLE_RATE as f32)).exp() as f64;
+    let release = (-1000.0 / (release_ms * SAMPLE_RATE as f32)).exp() as f64;
+
+    let mut envelope = 0.0;
+    for (sample, out) in input.iter().zip(output.iter_mut()) {
+        let rectified = sample.y.abs();
+        let coeff = if rectified > envelope { attack } else { release };
+        envelope = rectified + coeff * (envelope - rectified);
+        out.x = sample.x;
+        out.y = envelope;
+    }
+}
+
+pub fn decimate_to_columns(points: &[PlotPoint], min_x: f64, max_x: f64, columns: usize) -> Vec<PlotPoint> {
+    if columns == 0 {
+        return points.to_vec();
+    }
+
+    let mut decimated: Vec<PlotPoint> = Vec::with_capacity(columns.min(points.len()));
+    let mut last_column = None;
+    for point in points {
+        let column = ((point.x - min_x) / (max_x - min_x) * columns as f64).floor() as i64;
+        match decimated.last_mut() {
+            Some(last) if last_column == Some(column) => {
+                if point.y > last.y {
+                    *last = *point;
+                }
+            },
+            _ => {
+                decimated.push(*point);
+                last_column = Some(column);
+            }
+        }
+    }
+    decimated
+}
+
+// Searches back from `newest` over `length` samples, wrapping around the ring, so the most
+// recent rising crossing wins
+pub fn find_rising_zero_crossing(buffer: &[f32], newest: usize, length: usize) -> Option<usize> {
+    let size = buffer.len();
+    (0..length.min(size - 1))
+        .map(|k| (newest + size - k) % size)
+        .find(|&i| buffer[(i + size - 1) % size] < 0.0 && buffer[i] >= 0.0)
+}
+
+// Window one block (sample i read through `sample`) and transform it in place
+pub fn transform_block<const SIZE: usize>(
+    fft: &dyn Fft<f32>,
+    window: &[f32; SIZE],
+    sample: impl Fn(usize) -> f32,
+    spectrum: &mut [Complex32; SIZE]
+) {
+    for i in 0..SIZE {
+        spectrum[i] = Complex32::new(window[i] * sample(i), 0.0);
+    }
+    fft.process(spectrum);
+}
+
+// Move the smoothed norms toward the new frame and keep its phases; returns the strongest
+// smoothed bin up to Nyquist (the upper half only mirrors it) and its phase advance since the
+// previous frame
+pub fn smooth_spectrum(
+    spectrum: &[Complex32],
+    filtered: &mut [f64],
+    phases: &mut [f32],
+    smoothing: f64
+) -> (usize, f32) {
+    let mut max_norm = 0.0;
+    let mut max_norm_index = 0;
+    let mut max_norm_phase_diff = 0.0;
+    for (i, value) in spectrum.iter().enumerate() {
+        let (norm, phase) = value.to_polar();
+        filtered[i] += smoothing * (norm as f64 - filtered[i]);
+
+        let phase_diff = phase - phases[i];
+        phases[i] = phase;
+
+        if i <= spectrum.len() / 2 && filtered[i] > max_norm {
+            max_norm = filtered[i];
+            max_norm_index = i;
+            max_norm_phase_diff = phase_diff;
+        }
+    }
+    (max_norm_index, max_norm_phase_diff)
+}
+
+// Absolute amplitude of a bin norm, so a full scale sine reads 1.0 whatever the window
+pub fn bin_amplitude(window: &[f32], norm: f64) -> f64 {
+    2.0 / window.iter().sum::<f32>() as f64 * norm
+}
+
+// Frequency in cycles per sample from the strongest bin; the phase estimate picks whichever
+// phase unwrapping lands nearest the bin, given `dt` samples between the two frames
+pub fn estimate_frequency(estimator: FrequencyEstimator, peak_bin: usize, phase_diff: f32, dt: f32, size: usize) -> f32 {
+    let freq_est = peak_bin as f32 / size as f32;
+    match estimator {
+        FrequencyEstimator::PeakBin => freq_est,
+        FrequencyEstimator::PhaseDiff => {
+            let mut phase = 0.0;
+            let mut freq_prev = 0.0;
+            loop {
+                let freq = (phase_diff + phase) / (2.0 * PI * dt);
+                if freq > freq_est {
+                    if freq - freq_est < freq_est - freq_prev {
+                        break freq;
+                    } else {
+                        break freq_prev;
+                    };
+                }
+                freq_prev = freq;
+                phase += 2.0 * PI;
+            }
+        }
+    }
+}
+
+// Analyze a full scale sine through the same per-frame path as Context, for every built-in
+// window: a bin-centered tone must read 1.0 on its bin, and both estimators must find the
+// frequency of an off-bin tone from two frames a hop apart
+pub fn self_test<const SIZE: usize>() -> bool {
+    const LEVEL_TOLERANCE: f64 = 0.01;
+    const BIN_TOLERANCE: f32 = 0.6;
+    const PHASE_BIN_TOLERANCE: f32 = 0.05;
+
+    let fft = FftPlanner::new().plan_fft_forward(SIZE);
+    let hop = SIZE / 8;
+    let hz = |freq: f32| freq * SAMPLE_RATE as f32;
+    let mut passed = true;
+
+    for window_function in WindowFunction::options() {
+        let window = build_window_function::<SIZE>(window_function);
+        for (offset, check_level) in [(0.0, true), (0.3, false)] {
+            let frequency = (SIZE as f32 / 16.0 + offset) / SIZE as f32;
+            let mut channel = Channel::new();
+            channel.handle_command(Command::SetFrequency(frequency));
+            let samples = (0..(SIZE + hop)).map(|_| channel.process()).collect::<Vec<_>>();
+
+            let mut spectrum = [Complex32::default(); SIZE];
+            let mut filtered = [0.0; SIZE];
+            let mut phases = [0.0; SIZE];
+            transform_block(&*fft, &window, |i| samples[i], &mut spectrum);
+            smooth_spectrum(&spectrum, &mut filtered, &mut phases, 1.0);
+            transform_block(&*fft, &window, |i| samples[hop + i], &mut spectrum);
+            let (peak_bin, phase_diff) = smooth_spectrum(&spectrum, &mut filtered, &mut phases, 1.0);
+
+            let level = bin_amplitude(&window, filtered[peak_bin]);
+            let level_ok = !check_level || (level - 1.0).abs() < LEVEL_TOLERANCE;
+            let mut estimates_ok = true;
+            for estimator in [FrequencyEstimator::PeakBin, FrequencyEstimator::PhaseDiff] {
+                let estimate = estimate_frequency(estimator, peak_bin, phase_diff, hop as f32, SIZE);
+                let tolerance = match estimator {
+                    FrequencyEstimator::PeakBin => BIN_TOLERANCE,
+                    FrequencyEstimator::PhaseDiff => PHASE_BIN_TOLERANCE
+                } / SIZE as f32;
+                let ok = (estimate - frequency).abs() < tolerance;
+                println!(
+                    "self-test: {} window, {} estimate of {:.2} Hz: {:.2} Hz {}",
+                    window_function,
+                    estimator,
+                    hz(frequency),
+                    hz(estimate),
+                    if ok { "PASS" } else { "FAIL" }
+                );
+                estimates_ok &= ok;
+            }
+            if check_level {
+                println!(
+                    "self-test: {} window, level of {:.2} Hz: {:.4} {}",
+                    window_function,
+                    hz(frequency),
+                    level,
+                    if level_ok { "PASS" } else { "FAIL" }
+                );
+            }
+            passed &= level_ok && estimates_ok;
+        }
+    }
+    passed
+}
+
+pub fn mean_and_std_dev(values: &[f32]) -> (f32, f32) {
+    let n = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+    (mean, variance.sqrt())
+}
+
+// Least-squares fit of dB against log2 frequency over [min_x, max_x], in dB/octave
+pub fn fit_slope(points: &[PlotPoint], min_x: f64, max_x: f64, floor: f64) -> Option<f64> {
+    let selected = points.iter()
+        .filter(|point| point.x >= min_x && point.x <= max_x)
+        .map(|point| (point.x, magnitude_to_db(point.y, floor)))
+        .collect::<Vec<_>>();
+    if selected.len() < 3 {
+        return None;
+    }
+
+    let n = selected.len() as f64;
+    let mean_x = selected.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = selected.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance = selected.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum::<f64>();
+    let variance = selected.iter().map(|(x, _)| (x - mean_x).powi(2)).sum::<f64>();
+    if variance == 0.0 {
+        return None;
+    }
+    Some(covariance / variance)
+}
+
+// Ratio of the combined harmonic amplitudes to the fundamental's, from a full SIZE-bin spectrum
+pub fn total_harmonic_distortion(magnitude: &[PlotPoint], fundamental: f32, harmonics: usize) -> Option<f64> {
+    let size = magnitude.len();
+    let bin = |k: usize| (k as f32 * fundamental * size as f32).round() as usize;
+    let fundamental_magnitude = magnitude.get(bin(1)).filter(|_| bin(1) > 0)?.y;
+    if fundamental_magnitude <= 0.0 {
+        return None;
+    }
+    let harmonic_power = (2..=harmonics)
+        .map(bin)
+        .take_while(|&i| i < size / 2)
+        .map(|i| magnitude[i].y.powi(2))
+        .sum::<f64>();
+    Some(harmonic_power.sqrt() / fundamental_magnitude)
+}
+
+// Median bin level, which ignores the few bins holding tones
+pub fn noise_floor_db(magnitude: &[PlotPoint], floor: f64) -> f64 {
+    let mut levels = magnitude.iter()
+        .map(|point| magnitude_to_db(point.y, floor))
+        .collect::<Vec<_>>();
+    levels.sort_by(f64::total_cmp);
+    levels.get(levels.len() / 2).copied().unwrap_or(floor)
+}
+
+// Steps an input oscillator across a log-spaced range, recording the response at each step
+pub struct Sweep {
+    pub channel: usize,
+    pub start_hz: f32,
+    pub end_hz: f32,
+    pub points: usize,
+    pub response: Vec<PlotPoint>,
+    step: Option<usize>,
+    elapsed: usize,
+    restore_frequency: f32
+}
+
+impl Sweep {
+    pub fn new() -> Self {
+        Sweep {
+            channel: 0,
+            start_hz: 20.0,
+            end_hz: 20_000.0,
+            points: 32,
+            response: Vec::new(),
+            step: None,
+            elapsed: 0,
+            restore_frequency: 0.0
+        }
+    }
+
+    pub fn running(&self) -> bool {
+        self.step.is_some()
+    }
+
+    pub fn progress(&self) -> usize {
+        self.step.unwrap_or(0)
+    }
+
+    // Normalized (cycles per sample) frequency of a step
+    fn frequency(&self, step: usize) -> f32 {
+        let t = step as f32 / (self.points - 1).max(1) as f32;
+        self.start_hz * (self.end_hz / self.start_hz).powf(t) / SAMPLE_RATE as f32
+    }
+
+    // Returns the first frequency to set
+    pub fn start(&mut self, restore_frequency: f32) -> f32 {
+        self.response.clear();
+        self.restore_frequency = restore_frequency;
+        self.step = Some(0);
+        self.elapsed = 0;
+        self.frequency(0)
+    }
+
+    // Returns the original frequency to put back
+    pub fn stop(&mut self) -> f32 {
+        self.step = None;
+        self.restore_frequency
+    }
+
+    // Called once per analyzed block with the samples written since the previous one; once
+    // `settle` samples of the current step have passed, measures it and returns the next
+    // frequency to set
+    pub fn update(&mut self, samples: usize, settle: usize, amplitude_at: impl Fn(f32) -> f64) -> Option<f32> {
+        let step = self.step?;
+        self.elapsed += samples;
+        if self.elapsed < settle {
+            return None;
+        }
+
+        let frequency = self.frequency(step);
+        self.response.push(PlotPoint::new((frequency as f64).log2(), amplitude_at(frequency)));
+        if step + 1 >= self.points {
+            return Some(self.stop());
+        }
+        self.step = Some(step + 1);
+        self.elapsed = 0;
+        Some(self.frequency(step + 1))
+    }
+}
+
+impl Default for Sweep {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Normalized zero-lag correlation: +1 for identical signals, -1 for inverted ones
+pub fn correlation(a: &[f32], b: &[f32]) -> Option<f32> {
+    let ab = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let aa = a.iter().map(|x| x * x).sum::<f32>();
+    let bb = b.iter().map(|y| y * y).sum::<f32>();
+    (aa > 0.0 && bb > 0.0).then(|| ab / (aa * bb).sqrt())
+}
+
+// `input` holds `max_delay` extra samples of history before the span covered by `output`;
+// returns the lag in samples at which the input best lines up with the output.
+// Correlates in the frequency domain, zero-padded so the circular product doesn't wrap.
+pub fn estimate_delay(output: &[f32], input: &[f32], max_delay: usize) -> usize {
+    let length = (output.len() + input.len()).next_power_of_two();
+    let padded = |signal: &[f32]| {
+        let mut buffer = vec![Complex32::new(0.0, 0.0); length];
+        for (value, &sample) in buffer.iter_mut().zip(signal) {
+            value.re = sample;
+        }
+        buffer
+    };
+    let mut planner = FftPlanner::new();
+    let forward = planner.plan_fft_forward(length);
+    let mut a = padded(output);
+    let mut b = padded(input);
+    forward.process(&mut a);
+    forward.process(&mut b);
+
+    // IFFT(conj(A) * B)[m] = sum of output[i] * input[i + m]
+    let mut product = a.iter().zip(&b).map(|(x, y)| x.conj() * y).collect::<Vec<_>>();
+    planner.plan_fft_inverse(length).process(&mut product);
+
+    (0..=max_delay)
+        .max_by(|&m, &n| product[m].re.total_cmp(&product[n].re))
+        .map(|m| max_delay - m)
+        .unwrap_or(0)
+}
+
+// Magnitude-weighted mean frequency and spread around it, in Hz, over bins 1 to Nyquist of a
+// full spectrum of unscaled bin norms
+pub fn spectral_centroid_and_bandwidth(norms: &[f64]) -> Option<(f64, f64)> {
+    let size = norms.len();
+    let bins = || (1..=(size / 2)).map(|i| (i as f64 / size as f64 * SAMPLE_RATE as f64, norms[i]));
+    let total = bins().map(|(_, norm)| norm).sum::<f64>();
+    if total <= 0.0 {
+        return None;
+    }
+    let centroid = bins().map(|(hz, norm)| hz * norm).sum::<f64>() / total;
+    let variance = bins().map(|(hz, norm)| (hz - centroid).powi(2) * norm).sum::<f64>() / total;
+    Some((centroid, variance.sqrt()))
+}
+
+pub fn median(values: &[f32]) -> f32 {
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f32::total_cmp);
+    sorted.get(sorted.len() / 2).copied().unwrap_or(0.0)
+}
+
+pub fn scale_spectrum(
+    scaling: SpectrumScaling,
+    magnitude: &mut [PlotPoint],
+    average: &mut [f64],
+    max_magnitude: f64,
+    window_sum: f32
+) {
+    match scaling {
+        SpectrumScaling::PeakNormalized => {
+            for point in magnitude.iter_mut() {
+                point.y /= max_magnitude;
+            }
+        },
+        SpectrumScaling::Absolute => {
+            // A full scale sine lands at 1.0 regardless of the window
+            for point in magnitude.iter_mut() {
+                point.y *= 2.0 / window_sum as f64;
+            }
+        },
+        SpectrumScaling::PerBin => {
+            // An empty average is seeded from the current frame, so bins start level at 1.0
+            // instead of far above it, and silent bins stay at 0 rather than 0/0
+            for (point, average) in magnitude.iter_mut().zip(average.iter_mut()) {
+                if *average > 0.0 {
+                    *average += 0.01 * (point.y - *average);
+                } else {
+                    *average = point.y;
+                }
+                point.y = if *average > 0.0 { point.y / *average } else { 0.0 };
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zero_crossing_finds_most_recent() {
+        // Rising crossings at 2, 6 and 10
+        let buffer = [0.5, -1.0, 1.0, 0.5, -0.5, -1.0, 1.0, 0.5, -0.5, -1.0, 1.0, 0.5];
+        assert_eq!(find_rising_zero_crossing(&buffer, 11, 10), Some(10));
+        assert_eq!(find_rising_zero_crossing(&buffer, 9, 8), Some(6));
+        assert_eq!(find_rising_zero_crossing(&buffer, 5, 2), None);
+    }
+
+    #[test]
+    fn zero_crossing_wraps_around_the_ring() {
+        let buffer = [1.0, 0.5, 0.5, -1.0];
+        assert_eq!(find_rising_zero_crossing(&buffer, 1, 3), Some(0));
+    }
+
+    #[test]
+    fn fit_slope_measures_db_per_octave() {
+        // -6 dB per octave from 1/1024 to 1/4 cycles per sample
+        let points = (-10..=-2)
+            .map(|octave| PlotPoint::new(octave as f64, 10.0_f64.powf(-6.0 * octave as f64 / 20.0)))
+            .collect::<Vec<_>>();
+        let slope = fit_slope(&points, -10.0, -2.0, DEFAULT_DB_FLOOR).unwrap();
+        assert!((slope + 6.0).abs() < 1e-9, "slope {}", slope);
+        assert_eq!(fit_slope(&points, -3.0, -2.0, DEFAULT_DB_FLOOR), None);
+    }
+
+    #[test]
+    fn estimate_delay_finds_the_input_lag() {
+        let max_delay = 64;
+        let mut rng = crate::rng::Rng::new(1);
+        let signal = (0..(256 + max_delay)).map(|_| rng.next_f32() - 0.5).collect::<Vec<_>>();
+        // The output is the input `delay` samples later
+        for delay in [0, 5, 37, max_delay] {
+            let output = &signal[(max_delay - delay)..(max_delay - delay + 256)];
+            let input = &signal[..(256 + max_delay)];
+            assert_eq!(estimate_delay(output, input, max_delay), delay);
+        }
+    }
+
+    #[test]
+    fn weighting_is_unity_at_1khz() {
+        for weighting in [Weighting::Flat, Weighting::A, Weighting::C] {
+            let db = 20.0 * weighting_gain(weighting, 1000.0).log10();
+            assert!(db.abs() < 0.01, "{} at 1 kHz: {} dB", weighting, db);
+        }
+        // A-weighting is about -19 dB at 100 Hz, C-weighting close to flat
+        let a = 20.0 * weighting_gain(Weighting::A, 100.0).log10();
+        let c = 20.0 * weighting_gain(Weighting::C, 100.0).log10();
+        assert!((a + 19.1).abs() < 0.5, "A at 100 Hz: {} dB", a);
+        assert!((c + 0.3).abs() < 0.5, "C at 100 Hz: {} dB", c);
+    }
+
+    #[test]
+    fn scale_spectrum_modes() {
+        let points = |values: &[f64]| values.iter().map(|&y| PlotPoint::new(0.0, y)).collect::<Vec<_>>();
+        let ys = |points: &[PlotPoint]| points.iter().map(|point| point.y).collect::<Vec<_>>();
+        let mut average = [0.0; 3];
+
+        let mut magnitude = points(&[1.0, 4.0, 2.0]);
+        scale_spectrum(SpectrumScaling::PeakNormalized, &mut magnitude, &mut average, 4.0, 8.0);
+        assert_eq!(ys(&magnitude), [0.25, 1.0, 0.5]);
+
+        let mut magnitude = points(&[1.0, 4.0, 2.0]);
+        scale_spectrum(SpectrumScaling::Absolute, &mut magnitude, &mut average, 4.0, 8.0);
+        assert_eq!(ys(&magnitude), [0.25, 1.0, 0.5]);
+
+        // The first frame seeds the average, and silent bins stay at zero
+        let mut magnitude = points(&[1.0, 4.0, 0.0]);
+        scale_spectrum(SpectrumScaling::PerBin, &mut magnitude, &mut average, 4.0, 8.0);
+        assert_eq!(ys(&magnitude), [1.0, 1.0, 0.0]);
+        assert_eq!(average, [1.0, 4.0, 0.0]);
+    }
+
+    #[test]
+    fn median_picks_the_middle() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[5.0, 100.0, 1.0, 2.0, 3.0]), 3.0);
+        assert_eq!(median(&[]), 0.0);
+    }
+
+    #[test]
+    fn load_window_function_resamples_and_normalizes() {
+        let path = std::env::temp_dir().join(format!("dsp-test-window-{}.txt", std::process::id()));
+        std::fs::write(&path, "0, 2\n4").unwrap();
+        let path_str = path.to_str().unwrap();
+
+        assert_eq!(load_window_function::<5>(path_str, false).unwrap(), [0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(load_window_function::<3>(path_str, true).unwrap(), [0.0, 0.5, 1.0]);
+
+        std::fs::write(&path, "1, x").unwrap();
+        assert!(load_window_function::<3>(path_str, false).is_err());
+        std::fs::write(&path, " ").unwrap();
+        assert!(load_window_function::<3>(path_str, false).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,8 @@
+use dsp_test::registry::Registry;
+
+
+pub fn main() {
+    Registry::<2, 2>::with_examples()
+        .run()
+        .unwrap();
+}
@@ -1,27 +1,61 @@
 use dsp_test::Module;
+use dsp_test::examples::{Gain, Through};
 
 
-struct Through<const N: usize> {
-    values: [f32; N]
-}
+const MODULES: [&str; 2] = ["through", "gain"];
+const CHANNELS: [usize; 3] = [1, 2, 4];
+
 
-impl<const N: usize> Through<N> {
-    pub fn new() -> Self {
-        Through { values: [0.0; N] }
+fn run_module<const N: usize>(module: &str) -> eframe::Result {
+    match module {
+        "gain" => Gain::<N>::new(0.5).run(),
+        _ => Through::<N>::new().run()
     }
 }
 
-impl<const N: usize> Module<N, N> for Through<N> {
-    fn map_inputs(&mut self, input_buffer: &[f32; N]) {
-        self.values.copy_from_slice(input_buffer);
+fn usage() -> ! {
+    eprintln!(
+        "usage: test [--self-test] [--module {}] [--channels {}]",
+        MODULES.join("|"),
+        CHANNELS.map(|n| n.to_string()).join("|")
+    );
+    std::process::exit(2);
+}
+
+pub fn main() {
+    let mut self_test = false;
+    let mut module = "through".to_string();
+    let mut channels = 2;
+
+    // Unknown flags and missing or invalid values print the usage and exit non-zero
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--self-test" => self_test = true,
+            "--module" => {
+                module = args.next()
+                    .filter(|value| MODULES.contains(&value.as_str()))
+                    .unwrap_or_else(|| usage());
+            },
+            "--channels" => {
+                channels = args.next()
+                    .and_then(|value| value.parse().ok())
+                    .filter(|value| CHANNELS.contains(value))
+                    .unwrap_or_else(|| usage());
+            },
+            _ => usage()
+        }
     }
 
-    fn map_outputs(&mut self, output_buffer: &mut [f32; N]) {
-        output_buffer.copy_from_slice(&self.values);
+    // The self-test runs headless and reports through the exit code
+    if self_test {
+        std::process::exit(if dsp_test::self_test() { 0 } else { 1 });
     }
-}
 
-pub fn main() {
-    let module = Through::<2>::new();
-    module.run().unwrap();
-}
\ No newline at end of file
+    match channels {
+        1 => run_module::<1>(&module),
+        2 => run_module::<2>(&module),
+        4 => run_module::<4>(&module),
+        _ => usage()
+    }.unwrap();
+}
@@ -0,0 +1,260 @@
+use std::sync::OnceLock;
+
+use crate::Module;
+use crate::output::MAX_TAPS;
+
+
+// Both halves' debug taps side by side, the first's ahead of the second's, up to `MAX_TAPS`.
+// Values are gathered after every sample; the names never change, so they're joined once.
+struct Taps {
+    values: [f32; MAX_TAPS],
+    count: usize,
+    names: OnceLock<Vec<&'static str>>
+}
+
+impl Taps {
+    fn new() -> Self {
+        Taps {
+            values: [0.0; MAX_TAPS],
+            count: 0,
+            names: OnceLock::new()
+        }
+    }
+
+    fn collect(&mut self, first: &[f32], second: &[f32]) {
+        self.count = 0;
+        for &tap in first.iter().chain(second).take(MAX_TAPS) {
+            self.values[self.count] = tap;
+            self.count += 1;
+        }
+    }
+
+    fn values(&self) -> &[f32] {
+        &self.values[..self.count]
+    }
+
+    fn names(&self, first: &[&'static str], second: &[&'static str]) -> &[&'static str] {
+        self.names.get_or_init(|| first.iter().chain(second).take(MAX_TAPS).copied().collect())
+    }
+}
+
+
+pub struct Chain<A, B, const MID: usize> {
+    first: A,
+    second: B,
+    buffer: [f32; MID],
+    taps: Taps
+}
+
+impl<A, B, const MID: usize> Chain<A, B, MID> {
+    pub fn new(first: A, second: B) -> Self {
+        Chain {
+            first,
+            second,
+            buffer: [0.0; MID],
+            taps: Taps::new()
+        }
+    }
+}
+
+impl<A, B, const IN: usize, const MID: usize, const OUT: usize> Module<IN, OUT> for Chain<A, B, MID>
+where
+    A: Module<IN, MID>,
+    B: Module<MID, OUT>
+{
+    fn map_inputs(&mut self, input_buffer: &[f32; IN]) {
+        self.first.map_inputs(input_buffer);
+        self.first.map_outputs(&mut self.buffer);
+        self.second.map_inputs(&self.buffer);
+    }
+
+    fn map_outputs(&mut self, output_buffer: &mut [f32; OUT]) {
+        self.second.map_outputs(output_buffer);
+        self.taps.collect(self.first.debug_taps(), self.second.debug_taps());
+    }
+
+    fn debug_taps(&self) -> &[f32] {
+        self.taps.values()
+    }
+
+    fn debug_tap_names(&self) -> &[&'static str] {
+        self.taps.names(self.first.debug_tap_names(), self.second.debug_tap_names())
+    }
+}
+
+
+pub struct Parallel<A, B, const OA: usize, const OB: usize> {
+    first: A,
+    second: B,
+    first_buffer: [f32; OA],
+    second_buffer: [f32; OB],
+    taps: Taps
+}
+
+impl<A, B, const OA: usize, const OB: usize> Parallel<A, B, OA, OB> {
+    pub fn new(first: A, second: B) -> Self {
+        Parallel {
+            first,
+            second,
+            first_buffer: [0.0; OA],
+            second_buffer: [0.0; OB],
+            taps: Taps::new()
+        }
+    }
+}
+
+impl<A, B, const IN: usize, const OA: usize, const OB: usize, const OUT: usize> Module<IN, OUT> for Parallel<A, B, OA, OB>
+where
+    A: Module<IN, OA>,
+    B: Module<IN, OB>
+{
+    fn map_inputs(&mut self, input_buffer: &[f32; IN]) {
+        self.first.map_inputs(input_buffer);
+        self.second.map_inputs(input_buffer);
+    }
+
+    fn map_outputs(&mut self, output_buffer: &mut [f32; OUT]) {
+        const { assert!(OUT == OA + OB, "Parallel outputs must be OA + OB") };
+
+        self.first.map_outputs(&mut self.first_buffer);
+        self.second.map_outputs(&mut self.second_buffer);
+        output_buffer[..OA].copy_from_slice(&self.first_buffer);
+        output_buffer[OA..].copy_from_slice(&self.second_buffer);
+        self.taps.collect(self.first.debug_taps(), self.second.debug_taps());
+    }
+
+    fn debug_taps(&self) -> &[f32] {
+        self.taps.values()
+    }
+
+    fn debug_tap_names(&self) -> &[&'static str] {
+        self.taps.names(self.first.debug_tap_names(), self.second.debug_tap_names())
+    }
+}
+
+
+pub struct Sum<A, B, const OUT: usize> {
+    first: A,
+    second: B,
+    buffer: [f32; OUT],
+    taps: Taps
+}
+
+impl<A, B, const OUT: usize> Sum<A, B, OUT> {
+    pub fn new(first: A, second: B) -> Self {
+        Sum {
+            first,
+            second,
+            buffer: [0.0; OUT],
+            taps: Taps::new()
+        }
+    }
+}
+
+impl<A, B, const IN: usize, const OUT: usize> Module<IN, OUT> for Sum<A, B, OUT>
+where
+    A: Module<IN, OUT>,
+    B: Module<IN, OUT>
+{
+    fn map_inputs(&mut self, input_buffer: &[f32; IN]) {
+        self.first.map_inputs(input_buffer);
+        self.second.map_inputs(input_buffer);
+    }
+
+    fn map_outputs(&mut self, output_buffer: &mut [f32; OUT]) {
+        self.first.map_outputs(output_buffer);
+        self.second.map_outputs(&mut self.buffer);
+        for (out, second) in output_buffer.iter_mut().zip(self.buffer) {
+            *out += second;
+        }
+        self.taps.collect(self.first.debug_taps(), self.second.debug_taps());
+    }
+
+    fn debug_taps(&self) -> &[f32] {
+        self.taps.values()
+    }
+
+    fn debug_tap_names(&self) -> &[&'static str] {
+        self.taps.names(self.first.debug_tap_names(), self.second.debug_tap_names())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::examples::{Gain, Through};
+
+    fn process<M: Module<IN, OUT>, const IN: usize, const OUT: usize>(module: &mut M, input: [f32; IN]) -> [f32; OUT] {
+        let mut output = [0.0; OUT];
+        module.map_inputs(&input);
+        module.map_outputs(&mut output);
+        output
+    }
+
+    #[test]
+    fn chain_feeds_first_into_second() {
+        let mut chain = Chain::<_, _, 2>::new(Through::<2>::new(), Gain::<2>::new(0.5));
+        assert_eq!(process(&mut chain, [1.0, 2.0]), [0.5, 1.0]);
+    }
+
+    #[test]
+    fn parallel_concatenates_outputs() {
+        let mut parallel = Parallel::<_, _, 2, 2>::new(Through::<2>::new(), Gain::<2>::new(0.5));
+        assert_eq!(process(&mut parallel, [1.0, 2.0]), [1.0, 2.0, 0.5, 1.0]);
+    }
+
+    #[test]
+    fn sum_adds_outputs() {
+        let mut sum = Sum::<_, _, 2>::new(Through::<2>::new(), Gain::<2>::new(0.5));
+        assert_eq!(process(&mut sum, [1.0, 2.0]), [1.5, 3.0]);
+    }
+
+    // Passes its input through and taps it, scaled by `scale`
+    struct Tapped {
+        scale: f32,
+        name: &'static str,
+        value: [f32; 1]
+    }
+
+    impl Module<1, 1> for Tapped {
+        fn map_inputs(&mut self, input_buffer: &[f32; 1]) {
+            self.value = [self.scale * input_buffer[0]];
+        }
+
+        fn map_outputs(&mut self, output_buffer: &mut [f32; 1]) {
+            output_buffer[0] = self.value[0];
+        }
+
+        fn debug_taps(&self) -> &[f32] {
+            &self.value
+        }
+
+        fn debug_tap_names(&self) -> &[&'static str] {
+            std::slice::from_ref(&self.name)
+        }
+    }
+
+    fn tapped(scale: f32, name: &'static str) -> Tapped {
+        Tapped { scale, name, value: [0.0] }
+    }
+
+    #[test]
+    fn chain_forwards_taps_of_both() {
+        let mut chain = Chain::<_, _, 1>::new(tapped(2.0, "first"), tapped(3.0, "second"));
+        assert_eq!(process(&mut chain, [1.0]), [6.0]);
+        assert_eq!(Module::<1, 1>::debug_taps(&chain), [2.0, 6.0]);
+        assert_eq!(Module::<1, 1>::debug_tap_names(&chain), ["first", "second"]);
+    }
+
+    #[test]
+    fn parallel_forwards_taps_up_to_max() {
+        let left = Parallel::<_, _, 1, 1>::new(tapped(1.0, "a"), tapped(2.0, "b"));
+        let right = Parallel::<_, _, 1, 1>::new(tapped(3.0, "c"), tapped(4.0, "d"));
+        let four = Parallel::<_, _, 2, 2>::new(left, right);
+        let mut five = Parallel::<_, _, 4, 1>::new(four, tapped(5.0, "e"));
+        assert_eq!(process(&mut five, [1.0]), [1.0, 2.0, 3.0, 4.0, 5.0]);
+        assert_eq!(Module::<1, 5>::debug_taps(&five), [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(Module::<1, 5>::debug_tap_names(&five), ["a", "b", "c", "d"]);
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use strum::IntoEnumIterator;
+
+pub const APP_NAME: &str = "dsp-test";
+pub const SETTINGS_FILE: &str = "settings.cfg";
+pub const LAST_VIEW_FILE: &str = "last_view.cfg";
+pub const VIEW_PRESET_DIR: &str = "views";
+pub const VIEW_PRESET_EXTENSION: &str = "cfg";
+pub const CAPTURE_DIR: &str = "captures";
+pub const REPORT_DIR: &str = "reports";
+
+
+// Plain `key = value` lines, in the order they were set
+pub struct Config {
+    entries: Vec<(String, String)>
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config {
+            entries: Vec::new()
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let entries = fs::read_to_string(path)?
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Ok(Config { entries })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let contents = self.entries.iter()
+            .map(|(key, value)| format!("{} = {}\n", key, value))
+            .collect::<String>();
+        fs::write(path, contents)
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Display) {
+        let value = value.to_string();
+        match self.entries.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value,
+            None => self.entries.push((key.to_string(), value))
+        }
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn parse<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key)?.parse().ok()
+    }
+
+    // Enums are stored by their display name
+    pub fn parse_enum<T: IntoEnumIterator + Display>(&self, key: &str) -> Option<T> {
+        let value = self.get(key)?;
+        T::iter().find(|option| option.to_string() == value)
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Per-user config directory for the platform; without one nothing is persisted, rather than
+// scattering files into the working directory
+pub fn config_dir() -> io::Result<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Application Support"))
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|base| base.join(APP_NAME))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory found, settings are not saved"))
+}
+
+pub fn settings_path() -> io::Result<PathBuf> {
+    Ok(config_dir()?.join(SETTINGS_FILE))
+}
+
+pub fn last_view_path() -> io::Result<PathBuf> {
+    Ok(config_dir()?.join(LAST_VIEW_FILE))
+}
+
+// Settings shared across runs; a missing file or config directory just means defaults
+pub fn load_settings() -> Config {
+    settings_path()
+        .and_then(|path| Config::load(&path))
+        .unwrap_or_else(|_| Config::new())
+}
+
+pub fn save_setting(key: &str, value: impl Display) -> io::Result<()> {
+    let path = settings_path()?;
+    let mut settings = load_settings();
+    settings.set(key, value);
+    settings.save(&path)
+}
+
+// Creates `<prefix>_<ms>.<extension>` in `dir`, adding a counter when that name is already taken,
+// so exports never overwrite each other
+pub fn create_unique(dir: &Path, prefix: &str, extension: &str) -> io::Result<(PathBuf, File)> {
+    fs::create_dir_all(dir)?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_millis())
+        .unwrap_or(0);
+    let mut counter = 0;
+    loop {
+        let name = match counter {
+            0 => format!("{}_{}.{}", prefix, millis, extension),
+            n => format!("{}_{}_{}.{}", prefix, millis, n, extension)
+        };
+        let path = dir.join(name);
+        match File::create_new(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => counter += 1,
+            Err(err) => return Err(err)
+        }
+    }
+}
+
+pub fn capture_dir() -> io::Result<PathBuf> {
+    Ok(config_dir()?.join(CAPTURE_DIR))
+}
+
+pub fn report_dir() -> io::Result<PathBuf> {
+    Ok(config_dir()?.join(REPORT_DIR))
+}
+
+pub fn view_preset_dir() -> io::Result<PathBuf> {
+    Ok(config_dir()?.join(VIEW_PRESET_DIR))
+}
+
+// Preset names come from a text field, so anything that could leave the preset directory is refused
+pub fn view_preset_path(name: &str) -> io::Result<PathBuf> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\', ':']) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid preset name \"{}\"", name)));
+    }
+    Ok(view_preset_dir()?.join(format!("{}.{}", name, VIEW_PRESET_EXTENSION)))
+}
+
+pub fn view_preset_names() -> Vec<String> {
+    let mut names = view_preset_dir()
+        .and_then(fs::read_dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == VIEW_PRESET_EXTENSION))
+                .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_preset_path_rejects_escaping_names() {
+        for name in ["", ".", "..", ".hidden", "a/b", "../up", "a\\b", "C:evil"] {
+            assert!(view_preset_path(name).is_err(), "accepted {:?}", name);
+        }
+        if let Ok(dir) = view_preset_dir() {
+            assert_eq!(view_preset_path("Mine 2").unwrap(), dir.join("Mine 2.cfg"));
+        }
+    }
+
+    #[test]
+    fn create_unique_never_reuses_a_name() {
+        let dir = std::env::temp_dir().join(format!("dsp-test-unique-{}", std::process::id()));
+        let (first, _) = create_unique(&dir, "capture", "wav").unwrap();
+        let (second, _) = create_unique(&dir, "capture", "wav").unwrap();
+        assert_ne!(first, second);
+        assert!(first.exists() && second.exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
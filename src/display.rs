@@ -0,0 +1,272 @@
+use egui::{ThemePreference, Ui};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::config::{load_settings, settings_path};
+use crate::output::SAMPLE_RATE;
+
+pub const MAX_DECIMALS: usize = 6;
+
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, EnumIter)]
+pub enum FrequencyUnit {
+    #[default]
+    Normalized,
+    Hz
+}
+
+impl std::fmt::Display for FrequencyUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FrequencyUnit::Normalized => write!(f, "Normalized"),
+            FrequencyUnit::Hz => write!(f, "Hz")
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, EnumIter)]
+pub enum LevelUnit {
+    #[default]
+    Linear,
+    Decibels
+}
+
+impl std::fmt::Display for LevelUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LevelUnit::Linear => write!(f, "Linear"),
+            LevelUnit::Decibels => write!(f, "dB")
+        }
+    }
+}
+
+// Shared by the input and output widgets so every formatter agrees on units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DisplayPreferences {
+    pub frequency_unit: FrequencyUnit,
+    pub level_unit: LevelUnit,
+    pub decimals: usize,
+    pub theme: ThemePreference
+}
+
+impl DisplayPreferences {
+    pub fn new() -> Self {
+        DisplayPreferences {
+            frequency_unit: FrequencyUnit::default(),
+            level_unit: LevelUnit::default(),
+            decimals: 2,
+            theme: ThemePreference::System
+        }
+    }
+
+    pub fn load() -> Self {
+        let settings = load_settings();
+        let defaults = Self::new();
+        DisplayPreferences {
+            frequency_unit: settings.parse_enum("display.frequency_unit").unwrap_or(defaults.frequency_unit),
+            level_unit: settings.parse_enum("display.level_unit").unwrap_or(defaults.level_unit),
+            decimals: settings.parse("display.decimals").unwrap_or(defaults.decimals).min(MAX_DECIMALS),
+            theme: match settings.get("display.theme") {
+                Some("Dark") => ThemePreference::Dark,
+                Some("Light") => ThemePreference::Light,
+                _ => defaults.theme
+            }
+        }
+    }
+
+    pub fn save(&self) -> std::io::Result<()> {
+        let mut settings = load_settings();
+        settings.set("display.frequency_unit", self.frequency_unit);
+        settings.set("display.level_unit", self.level_unit);
+        settings.set("display.decimals", self.decimals);
+        settings.set("display.theme", format!("{:?}", self.theme));
+        settings.save(&settings_path()?)
+    }
+
+    // Normalized frequencies are small, so they get two extra places to stay readable
+    pub fn format_frequency(&self, normalized: f64) -> String {
+        match self.frequency_unit {
+            FrequencyUnit::Normalized => format!("{:.*}", self.decimals + 2, normalized),
+            FrequencyUnit::Hz => format!("{:.*} Hz", self.decimals, normalized * SAMPLE_RATE as f64)
+        }
+    }
+
+    pub fn parse_frequency(&self, text: &str) -> Option<f64> {
+        let text = text.trim();
+        match text.strip_suffix("Hz") {
+            Some(hz) => parse_number(hz).map(|hz| hz / SAMPLE_RATE as f64),
+            None => parse_number(text).map(|value| match self.frequency_unit {
+                FrequencyUnit::Normalized => value,
+                FrequencyUnit::Hz => value / SAMPLE_RATE as f64
+            })
+        }
+    }
+
+    pub fn format_gain(&self, gain: f64) -> String {
+        match self.level_unit {
+            LevelUnit::Linear => format!("{:.*}", self.decimals, gain),
+            LevelUnit::Decibels => self.format_db(gain)
+        }
+    }
+
+    pub fn parse_gain(&self, text: &str) -> Option<f64> {
+        self.parse_db(text).or_else(|| match self.level_unit {
+            LevelUnit::Linear => parse_number(text),
+            LevelUnit::Decibels => parse_number(text).map(db_to_gain)
+        })
+    }
+
+    pub fn format_percent(&self, fraction: f64) -> String {
+        format!("{:.*}%", self.decimals, 100.0 * fraction)
+    }
+
+    pub fn format_number(&self, value: f64) -> String {
+        format!("{:.*}", self.decimals, value)
+    }
+
+    pub fn format_volume(&self, volume: f64) -> String {
+        match self.level_unit {
+            LevelUnit::Linear => self.format_percent(volume),
+            LevelUnit::Decibels => self.format_db(volume)
+        }
+    }
+
+    pub fn parse_volume(&self, text: &str) -> Option<f64> {
+        let text = text.trim();
+        if text.ends_with('%') {
+            return parse_percent(text);
+        }
+        self.parse_db(text).or_else(|| match self.level_unit {
+            LevelUnit::Linear => parse_percent(text),
+            LevelUnit::Decibels => parse_number(text).map(db_to_gain)
+        })
+    }
+
+    fn format_db(&self, gain: f64) -> String {
+        if gain <= 0.0 {
+            "-inf dB".to_string()
+        } else {
+            format!("{:.*} dB", self.decimals, 20.0 * gain.log10())
+        }
+    }
+
+    fn parse_db(&self, text: &str) -> Option<f64> {
+        let db = text.trim().strip_suffix("dB")?.trim();
+        match db {
+            "-inf" => Some(0.0),
+            _ => parse_number(db).map(db_to_gain)
+        }
+    }
+
+    pub fn render(&mut self, ui: &mut Ui) {
+        ui.heading("Display");
+        let previous = *self;
+        egui::Grid::new("DisplayOptions")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Frequency:");
+                egui::ComboBox::from_id_salt("FrequencyUnitSelect")
+                    .selected_text(self.frequency_unit.to_string())
+                    .show_ui(ui, |ui| {
+                        for unit in FrequencyUnit::iter() {
+                            ui.selectable_value(&mut self.frequency_unit, unit, unit.to_string());
+                        }
+                    });
+
+                ui.end_row();
+
+                ui.label("Level:");
+                egui::ComboBox::from_id_salt("LevelUnitSelect")
+                    .selected_text(self.level_unit.to_string())
+                    .show_ui(ui, |ui| {
+                        for unit in LevelUnit::iter() {
+                            ui.selectable_value(&mut self.level_unit, unit, unit.to_string());
+                        }
+                    });
+
+                ui.end_row();
+
+                ui.label("Decimals:");
+                ui.add(egui::DragValue::new(&mut self.decimals).range(0..=MAX_DECIMALS));
+
+                ui.end_row();
+
+                ui.label("Theme:");
+                ui.horizontal(|ui| self.theme.radio_buttons(ui));
+
+                ui.end_row();
+            });
+        if self.theme != previous.theme {
+            ui.ctx().set_theme(self.theme);
+        }
+        if *self != previous {
+            let _ = self.save();
+        }
+    }
+}
+
+impl Default for DisplayPreferences {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn db_to_gain(db: f64) -> f64 {
+    10.0_f64.powf(db / 20.0)
+}
+
+pub fn parse_number(text: &str) -> Option<f64> {
+    text.trim().parse().ok()
+}
+
+pub fn parse_percent(text: &str) -> Option<f64> {
+    parse_number(text.trim().trim_end_matches('%')).map(|p| p / 100.0)
+}
+
+pub fn parse_ms_to_samples(text: &str) -> Option<f64> {
+    parse_number(text.trim().trim_end_matches("ms"))
+        .map(|ms| (ms * SAMPLE_RATE as f64 / 1000.0).round())
+}
+
+pub fn parse_seconds(text: &str) -> Option<f64> {
+    parse_number(text.trim().trim_end_matches('s'))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_helpers_accept_units_and_whitespace() {
+        assert_eq!(parse_number(" 1.5 "), Some(1.5));
+        assert_eq!(parse_number("abc"), None);
+        assert_eq!(parse_percent("50%"), Some(0.5));
+        assert_eq!(parse_percent(" 25 "), Some(0.25));
+        assert_eq!(parse_ms_to_samples("10ms"), Some(480.0));
+        assert_eq!(parse_ms_to_samples("0.01 ms"), Some(0.0));
+        assert_eq!(parse_seconds("2.5 s"), Some(2.5));
+        assert_eq!(parse_seconds("s"), None);
+    }
+
+    #[test]
+    fn parse_frequency_follows_unit() {
+        let mut display = DisplayPreferences::new();
+        assert_eq!(display.parse_frequency("0.25"), Some(0.25));
+        assert_eq!(display.parse_frequency("4800 Hz"), Some(0.1));
+        display.frequency_unit = FrequencyUnit::Hz;
+        assert_eq!(display.parse_frequency("4800"), Some(0.1));
+    }
+
+    #[test]
+    fn parse_levels_accept_db() {
+        let mut display = DisplayPreferences::new();
+        assert_eq!(display.parse_gain("0 dB"), Some(1.0));
+        assert_eq!(display.parse_gain("-inf dB"), Some(0.0));
+        assert_eq!(display.parse_gain("0.5"), Some(0.5));
+        assert_eq!(display.parse_volume("50%"), Some(0.5));
+        display.level_unit = LevelUnit::Decibels;
+        assert_eq!(display.parse_gain("0"), Some(1.0));
+        assert_eq!(display.parse_volume("50%"), Some(0.5));
+    }
+}
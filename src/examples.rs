@@ -0,0 +1,53 @@
+use crate::Module;
+
+
+// Built-in example modules, shared by the binaries and Registry::with_examples
+
+pub struct Through<const N: usize> {
+    values: [f32; N]
+}
+
+impl<const N: usize> Through<N> {
+    pub fn new() -> Self {
+        Through { values: [0.0; N] }
+    }
+}
+
+impl<const N: usize> Default for Through<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Module<N, N> for Through<N> {
+    fn map_inputs(&mut self, input_buffer: &[f32; N]) {
+        self.values.copy_from_slice(input_buffer);
+    }
+
+    fn map_outputs(&mut self, output_buffer: &mut [f32; N]) {
+        output_buffer.copy_from_slice(&self.values);
+    }
+}
+
+pub struct Gain<const N: usize> {
+    gain: f32,
+    values: [f32; N]
+}
+
+impl<const N: usize> Gain<N> {
+    pub fn new(gain: f32) -> Self {
+        Gain { gain, values: [0.0; N] }
+    }
+}
+
+impl<const N: usize> Module<N, N> for Gain<N> {
+    fn map_inputs(&mut self, input_buffer: &[f32; N]) {
+        for (value, input) in self.values.iter_mut().zip(input_buffer) {
+            *value = self.gain * input;
+        }
+    }
+
+    fn map_outputs(&mut self, output_buffer: &mut [f32; N]) {
+        output_buffer.copy_from_slice(&self.values);
+    }
+}
@@ -5,7 +5,14 @@ use rtrb::Producer;
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use crate::output::ControlMessage;
+use crate::config::Config;
+use crate::display::{parse_number, parse_percent, DisplayPreferences};
+use crate::output::{ControlMessage, SAMPLE_RATE};
+use crate::rng::Rng;
+
+pub const CLICK_LENGTH: usize = SAMPLE_RATE / 1000;
+pub const MAX_UNISON_VOICES: usize = 7;
+pub const MAX_HARMONICS: usize = 8;
 
 
 #[derive(Clone, Copy, EnumIter)]
@@ -13,7 +20,11 @@ pub enum Wave {
     Sine,
     RampUp,
     RampDown,
+    Triangle,
     Square { pw: f32 },
+    Click { bpm: f32, subdivision: usize, accent: bool },
+    Unison { voices: usize, detune: f32, shape: VoiceShape },
+    Harmonics { amplitudes: [f32; MAX_HARMONICS] },
     Const
 }
 
@@ -31,12 +42,32 @@ impl std::fmt::Display for Wave {
             Wave::Sine => write!(f, "Sine"),
             Wave::RampUp => write!(f, "Ramp Up"),
             Wave::RampDown => write!(f, "Ramp Down"),
+            Wave::Triangle => write!(f, "Triangle"),
             Wave::Square { .. } => write!(f, "Square"),
+            Wave::Click { .. } => write!(f, "Click"),
+            Wave::Unison { .. } => write!(f, "Unison"),
+            Wave::Harmonics { .. } => write!(f, "Harmonics"),
             Wave::Const => write!(f, "Const")
         }
     }
 }
 
+#[derive(Clone, Copy, Default, PartialEq, EnumIter)]
+pub enum VoiceShape {
+    #[default]
+    Saw,
+    Sine
+}
+
+impl std::fmt::Display for VoiceShape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VoiceShape::Saw => write!(f, "Saw"),
+            VoiceShape::Sine => write!(f, "Sine")
+        }
+    }
+}
+
 #[derive(Clone, Copy)]
 pub struct Channel {
     wave: Wave,
@@ -44,7 +75,12 @@ pub struct Channel {
     frequency: f32,
     scale: f32,
     offset: f32,
-    enabled: bool
+    enabled: bool,
+    clamp: bool,
+    skew: f32,
+    click_counter: usize,
+    click_beat: usize,
+    unison_phases: [f32; MAX_UNISON_VOICES]
 }
 
 impl Channel {
@@ -55,7 +91,12 @@ impl Channel {
             frequency: 0.0022,
             scale: 1.0,
             offset: 0.0,
-            enabled: true
+            enabled: true,
+            clamp: false,
+            skew: 0.5,
+            click_counter: 0,
+            click_beat: 0,
+            unison_phases: [0.0; MAX_UNISON_VOICES]
         }
     }
 
@@ -72,7 +113,15 @@ impl Channel {
             Command::SetEnabled =>
                 self.enabled = true,
             Command::SetDisabled =>
-                self.enabled = false
+                self.enabled = false,
+            Command::SetClamp(clamp) =>
+                self.clamp = clamp,
+            Command::SetSkew(skew) =>
+                self.skew = skew.clamp(0.01, 0.99),
+            Command::SetPhase(phase) =>
+                self.phase = phase,
+            Command::SetUnisonPhases(phases) =>
+                self.unison_phases = phases
         }
     }
 
@@ -82,29 +131,124 @@ impl Channel {
             self.phase -= 1.0;
         }
 
+        if let Wave::Click { bpm, subdivision, .. } = self.wave {
+            let subdivision = subdivision.max(1);
+            let period = (60.0 * SAMPLE_RATE as f32 / (bpm * subdivision as f32)) as usize;
+            self.click_counter += 1;
+            if self.click_counter >= period.max(CLICK_LENGTH) {
+                self.click_counter = 0;
+                self.click_beat = (self.click_beat + 1) % subdivision;
+            }
+        }
+
+        // Voices are spread evenly across ±detune cents around the base frequency
+        let mut unison = 0.0;
+        if let Wave::Unison { voices, detune, shape } = self.wave {
+            let voices = voices.clamp(1, MAX_UNISON_VOICES);
+            for voice in 0..voices {
+                let spread = if voices > 1 {
+                    2.0 * voice as f32 / (voices - 1) as f32 - 1.0
+                } else {
+                    0.0
+                };
+                let phase = &mut self.unison_phases[voice];
+                *phase += self.frequency * (spread * detune / 1200.0).exp2();
+                if *phase >= 1.0 {
+                    *phase -= 1.0;
+                }
+                unison += match shape {
+                    VoiceShape::Saw => 2.0 * *phase - 1.0,
+                    VoiceShape::Sine => (2.0 * PI * *phase).sin()
+                };
+            }
+            unison /= voices as f32;
+        }
+
         if !self.enabled {
             return 0.0;
         }
 
+        // Skew moves the midpoint of the cycle, so a triangle peaks at `skew` instead of 0.5
+        let skewed = if self.phase < self.skew {
+            0.5 * self.phase / self.skew
+        } else {
+            0.5 + 0.5 * (self.phase - self.skew) / (1.0 - self.skew)
+        };
+
         let sample = match self.wave {
             Wave::Sine =>
                 (2.0 * PI * self.phase).sin(),
             Wave::RampUp =>
-                2.0 * self.phase - 1.0,
+                2.0 * skewed - 1.0,
             Wave::RampDown =>
-                1.0 - 2.0 * self.phase,
+                1.0 - 2.0 * skewed,
+            Wave::Triangle =>
+                1.0 - 4.0 * (skewed - 0.5).abs(),
             Wave::Square { pw } =>
                 if self.phase < pw {
                     1.0
                 } else {
                     -1.0
                 },
+            Wave::Click { accent, .. } =>
+                if self.click_counter >= CLICK_LENGTH {
+                    0.0
+                } else if accent && self.click_beat != 0 {
+                    0.5
+                } else {
+                    1.0
+                },
+            Wave::Unison { .. } =>
+                unison,
+            Wave::Harmonics { amplitudes } =>
+                self.harmonics(&amplitudes),
             Wave::Const =>
                 0.0
         };
         
-        self.scale * sample + self.offset
+        let out = self.scale * sample + self.offset;
+        if self.clamp {
+            out.clamp(-1.0, 1.0)
+        } else {
+            out
+        }
+    }
+
+    // Harmonics above Nyquist are skipped, and the sum is scaled by the total amplitude
+    // so the series can never exceed the channel scale
+    fn harmonics(&self, amplitudes: &[f32; MAX_HARMONICS]) -> f32 {
+        let total = amplitudes.iter().map(|a| a.abs()).sum::<f32>();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let mut sum = 0.0;
+        for (n, amplitude) in amplitudes.iter().enumerate() {
+            let harmonic = (n + 1) as f32;
+            if harmonic * self.frequency >= 0.5 {
+                break;
+            }
+            sum += amplitude * (2.0 * PI * harmonic * self.phase).sin();
+        }
+        sum / total
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.scale + self.offset.abs()
+    }
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn identity_mixer<const N: usize>() -> [[f32; N]; N] {
+    let mut mixer = [[0.0; N]; N];
+    for (i, row) in mixer.iter_mut().enumerate() {
+        row[i] = 1.0;
     }
+    mixer
 }
 
 pub enum Event<const N: usize> {
@@ -117,18 +261,33 @@ pub enum Command {
     SetScale(f32),
     SetOffset(f32),
     SetEnabled,
-    SetDisabled
+    SetDisabled,
+    SetClamp(bool),
+    SetSkew(f32),
+    SetPhase(f32),
+    SetUnisonPhases([f32; MAX_UNISON_VOICES])
 }
 
 
 pub struct Widget<const N: usize> {
-    models: [Channel; N]
+    models: [Channel; N],
+    mixer: [[f32; N]; N],
+    random_phase: bool,
+    rng: Rng
 }
 
 impl<const N: usize> Widget<N> {
     pub fn new() -> Self {
         Widget {
-            models: [Channel::new(); N]
+            models: [Channel::new(); N],
+            mixer: identity_mixer(),
+            random_phase: false,
+            rng: Rng::new(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|t| t.as_nanos() as u64)
+                    .unwrap_or(1)
+            )
         }
     }
 
@@ -136,7 +295,40 @@ impl<const N: usize> Widget<N> {
         self.models = models;
     }
 
-    fn render_channel(&mut self, index: usize, ui: &mut Ui, sender: &mut Producer<ControlMessage>) {
+    pub fn frequency(&self, channel: usize) -> f32 {
+        self.models[channel].frequency
+    }
+
+    pub fn settings(&self) -> Config {
+        let mut config = Config::new();
+        for (i, model) in self.models.iter().enumerate() {
+            config.set(&format!("input{}.enabled", i), model.enabled);
+            config.set(&format!("input{}.wave", i), model.wave);
+            config.set(&format!("input{}.frequency_hz", i), model.frequency * SAMPLE_RATE as f32);
+            config.set(&format!("input{}.scale", i), model.scale);
+            config.set(&format!("input{}.offset", i), model.offset);
+            config.set(&format!("input{}.skew", i), model.skew);
+            config.set(&format!("input{}.clamp", i), model.clamp);
+        }
+        config
+    }
+
+    // Unison voices get their own random phases, so they don't start out summing coherently
+    fn randomize_phase(&mut self, channel: usize, sender: &mut Producer<ControlMessage>) {
+        sender.push(ControlMessage::InputControl {
+            channel,
+            command: Command::SetPhase(self.rng.next_f32())
+        }).unwrap();
+        sender.push(ControlMessage::InputControl {
+            channel,
+            command: Command::SetUnisonPhases(std::array::from_fn(|_| self.rng.next_f32()))
+        }).unwrap();
+    }
+
+    fn render_channel(&mut self, index: usize, ui: &mut Ui, sender: &mut Producer<ControlMessage>, display: &DisplayPreferences) {
+        let peak = self.models[index].peak();
+        let over_range = peak > 1.0;
+
         egui::Grid::new(index)
             .striped(true)
             .show(ui, |ui| {
@@ -152,6 +344,9 @@ impl<const N: usize> Widget<N> {
                                 false => Command::SetDisabled,
                             }
                         }).unwrap();
+                        if self.models[index].enabled && self.random_phase {
+                            self.randomize_phase(index, sender);
+                        }
                     };
                 });
 
@@ -162,7 +357,8 @@ impl<const N: usize> Widget<N> {
                     if ui.add(
                         egui::Slider::new(&mut self.models[index].frequency, 0.0..=5e-1)
                             .logarithmic(true)
-                            .custom_formatter(|f, _| format!("{:.4}", f))
+                            .custom_formatter(|f, _| display.format_frequency(f))
+                            .custom_parser(|text| display.parse_frequency(text))
                     ).changed() {
                         sender.push(ControlMessage::InputControl {
                             channel: index,
@@ -175,9 +371,13 @@ impl<const N: usize> Widget<N> {
 
                 ui.label("Scale:");
                 ui.horizontal(|ui| {
+                    if over_range {
+                        ui.visuals_mut().override_text_color = Some(ui.visuals().error_fg_color);
+                    }
                     if ui.add(
                         egui::Slider::new(&mut self.models[index].scale, 0.0..=1.0)
-                            .custom_formatter(|f, _| format!("{:.2}", f))
+                            .custom_formatter(|f, _| display.format_gain(f))
+                            .custom_parser(|text| display.parse_gain(text))
                     ).changed() {
                         sender.push(ControlMessage::InputControl {
                             channel: index,
@@ -190,9 +390,13 @@ impl<const N: usize> Widget<N> {
 
                 ui.label("Offset:");
                 ui.horizontal(|ui| {
+                    if over_range {
+                        ui.visuals_mut().override_text_color = Some(ui.visuals().error_fg_color);
+                    }
                     if ui.add(
                         egui::Slider::new(&mut self.models[index].offset, -1.0..=1.0)
-                            .custom_formatter(|f, _| format!("{:.2}", f))
+                            .custom_formatter(|f, _| format!("{:.*}", display.decimals, f))
+                            .custom_parser(parse_number)
                     ).changed() {
                         sender.push(ControlMessage::InputControl {
                             channel: index,
@@ -203,10 +407,29 @@ impl<const N: usize> Widget<N> {
 
                 ui.end_row();
 
+                ui.label("Peak:");
+                ui.horizontal(|ui| {
+                    if over_range {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("{} ⚠", display.format_number(peak as f64)));
+                    } else {
+                        ui.label(display.format_number(peak as f64));
+                    }
+                    if ui.add(
+                        egui::Checkbox::new(&mut self.models[index].clamp, "Clamp")
+                    ).changed() {
+                        sender.push(ControlMessage::InputControl {
+                            channel: index,
+                            command: Command::SetClamp(self.models[index].clamp)
+                        }).unwrap();
+                    };
+                });
+
+                ui.end_row();
+
                 ui.label("Wave:");
                 ui.horizontal(|ui| {
                     egui::ComboBox::from_id_salt(index)
-                        .selected_text(format!("{}", self.models[index].wave.to_string()))
+                        .selected_text(self.models[index].wave.to_string())
                         .show_ui(ui, |ui| {
                             for wave in Wave::iter() {
                                 if ui.add(
@@ -217,6 +440,19 @@ impl<const N: usize> Widget<N> {
                                 ).clicked() {
                                     self.models[index].wave = match wave {
                                         Wave::Square { .. } => Wave::Square { pw: 0.5 },
+                                        Wave::Click { .. } => Wave::Click {
+                                            bpm: 120.0,
+                                            subdivision: 1,
+                                            accent: true
+                                        },
+                                        Wave::Unison { .. } => Wave::Unison {
+                                            voices: MAX_UNISON_VOICES,
+                                            detune: 20.0,
+                                            shape: VoiceShape::Saw
+                                        },
+                                        Wave::Harmonics { .. } => Wave::Harmonics {
+                                            amplitudes: std::array::from_fn(|n| 1.0 / (n + 1) as f32)
+                                        },
                                         other => other
                                     };
                                     sender.push(ControlMessage::InputControl {
@@ -236,7 +472,8 @@ impl<const N: usize> Widget<N> {
                     if let Wave::Square { pw } = &mut self.models[index].wave {
                         if ui.add(
                             egui::Slider::new(pw, 0.0..=1.0)
-                                .custom_formatter(|pw, _| format!("{:.0}%", 100.0 * pw))
+                                .custom_formatter(|pw, _| display.format_percent(pw))
+                                .custom_parser(parse_percent)
                         ).changed() {
                             sender.push(ControlMessage::InputControl {
                                 channel: index,
@@ -249,15 +486,185 @@ impl<const N: usize> Widget<N> {
                 });
 
                 ui.end_row();
+
+                ui.label("Skew:");
+                ui.horizontal(|ui| {
+                    if matches!(self.models[index].wave, Wave::RampUp | Wave::RampDown | Wave::Triangle) {
+                        if ui.add(
+                            egui::Slider::new(&mut self.models[index].skew, 0.01..=0.99)
+                                .custom_formatter(|skew, _| display.format_percent(skew))
+                                .custom_parser(parse_percent)
+                        ).changed() {
+                            sender.push(ControlMessage::InputControl {
+                                channel: index,
+                                command: Command::SetSkew(self.models[index].skew)
+                            }).unwrap();
+                        };
+                    } else {
+                        ui.label("—-");
+                    }
+                });
+
+                ui.end_row();
+
+                ui.label("BPM:");
+                ui.horizontal(|ui| {
+                    if let Wave::Click { bpm, .. } = &mut self.models[index].wave {
+                        if ui.add(
+                            egui::Slider::new(bpm, 20.0..=300.0)
+                                .custom_formatter(|bpm, _| display.format_number(bpm))
+                                .custom_parser(parse_number)
+                        ).changed() {
+                            sender.push(ControlMessage::InputControl {
+                                channel: index,
+                                command: Command::SetWave(self.models[index].wave)
+                            }).unwrap();
+                        };
+                    } else {
+                        ui.label("—-");
+                    }
+                });
+
+                ui.end_row();
+
+                ui.label("Subdivision:");
+                ui.horizontal(|ui| {
+                    if let Wave::Click { subdivision, accent, .. } = &mut self.models[index].wave {
+                        let mut changed = ui.add(
+                            egui::Slider::new(subdivision, 1..=8)
+                        ).changed();
+                        changed |= ui.add(
+                            egui::Checkbox::new(accent, "Accent")
+                        ).changed();
+                        if changed {
+                            sender.push(ControlMessage::InputControl {
+                                channel: index,
+                                command: Command::SetWave(self.models[index].wave)
+                            }).unwrap();
+                        };
+                    } else {
+                        ui.label("—-");
+                    }
+                });
+
+                ui.end_row();
+
+                ui.label("Voices:");
+                ui.horizontal(|ui| {
+                    if let Wave::Unison { voices, detune, shape } = &mut self.models[index].wave {
+                        let mut changed = ui.add(
+                            egui::Slider::new(voices, 1..=MAX_UNISON_VOICES)
+                        ).changed();
+                        changed |= ui.add(
+                            egui::Slider::new(detune, 0.0..=100.0)
+                                .custom_formatter(|detune, _| format!("±{} ct", display.format_number(detune)))
+                                .custom_parser(parse_number)
+                        ).changed();
+                        for option in VoiceShape::iter() {
+                            changed |= ui.selectable_value(shape, option, option.to_string()).changed();
+                        }
+                        if changed {
+                            sender.push(ControlMessage::InputControl {
+                                channel: index,
+                                command: Command::SetWave(self.models[index].wave)
+                            }).unwrap();
+                        };
+                    } else {
+                        ui.label("—-");
+                    }
+                });
+
+                ui.end_row();
+
+                ui.label("Harmonics:");
+                ui.horizontal(|ui| {
+                    if let Wave::Harmonics { amplitudes } = &mut self.models[index].wave {
+                        ui.spacing_mut().slider_width = 60.0;
+                        let mut changed = false;
+                        for (n, amplitude) in amplitudes.iter_mut().enumerate() {
+                            changed |= ui.add(
+                                egui::Slider::new(amplitude, 0.0..=1.0)
+                                    .vertical()
+                                    .show_value(false)
+                            )
+                                .on_hover_text(format!("H{}: {:.*}", n + 1, display.decimals, amplitude))
+                                .changed();
+                        }
+                        if changed {
+                            sender.push(ControlMessage::InputControl {
+                                channel: index,
+                                command: Command::SetWave(self.models[index].wave)
+                            }).unwrap();
+                        };
+                    } else {
+                        ui.label("—-");
+                    }
+                });
+
+                ui.end_row();
+            });
+    }
+
+    fn render_mixer(&mut self, ui: &mut Ui, sender: &mut Producer<ControlMessage>, display: &DisplayPreferences) {
+        egui::Grid::new("InputMixer")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Input");
+                for channel in 0..N {
+                    ui.label(format!("Ch {}", channel));
+                }
+
+                ui.end_row();
+
+                for input in 0..N {
+                    ui.label(input.to_string());
+                    for channel in 0..N {
+                        if ui.add(
+                            egui::DragValue::new(&mut self.mixer[input][channel])
+                                .range(-2.0..=2.0)
+                                .speed(0.01)
+                                .fixed_decimals(display.decimals)
+                        ).changed() {
+                            sender.push(ControlMessage::InputMix {
+                                input,
+                                channel,
+                                gain: self.mixer[input][channel]
+                            }).unwrap();
+                        };
+                    }
+
+                    ui.end_row();
+                }
             });
     }
 
-    pub fn render(&mut self, ui: &mut Ui, sender: &mut Producer<ControlMessage>) {
+    pub fn render(&mut self, ui: &mut Ui, sender: &mut Producer<ControlMessage>, display: &DisplayPreferences) {
         ui.heading("Inputs");
         ui.separator();
         for i in 0..N {
-            self.render_channel(i, ui, sender);
+            self.render_channel(i, ui, sender, display);
             ui.separator();
         }
+
+        ui.label("Mixer:");
+        self.render_mixer(ui, sender, display);
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.random_phase, "Random Phase")
+                .on_hover_text("Randomize a channel's phase when it is enabled");
+            if ui.button("Randomize Phases").clicked() {
+                for channel in 0..N {
+                    self.randomize_phase(channel, sender);
+                }
+            }
+        });
+        ui.separator();
+    }
+}
+
+impl<const N: usize> Default for Widget<N> {
+    fn default() -> Self {
+        Self::new()
     }
 }
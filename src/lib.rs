@@ -1,41 +1,117 @@
 pub mod input;
 pub mod output;
 pub mod analyze;
+pub mod combine;
+pub mod config;
+pub mod display;
+pub mod examples;
+pub mod record;
+pub mod registry;
+pub mod report;
+pub mod rng;
 
-use std::f32::consts::PI;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use cpal::{traits::StreamTrait, Stream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use cpal::{traits::StreamTrait, HostId, Stream};
 use eframe::egui;
-use egui::Vec2b;
-use egui_plot::{Line, Plot, PlotBounds, PlotPoint};
+use egui::{Color32, Stroke, Vec2b};
+use strum::IntoEnumIterator;
+use egui_plot::{Legend, Line, LineStyle, Plot, PlotBounds, PlotPoint, PlotPoints, PlotUi, VLine};
 use rtrb::{Consumer, Producer, RingBuffer};
 use rustfft::num_complex::Complex32;
-use rustfft::Fft;
-use rustfft::{algorithm::Radix4, FftDirection};
+use rustfft::{Fft, FftPlanner};
 
 use crate::input::{
+    Command as InputCommand,
     Event,
     Widget as InputWidget
 };
 use crate::output::{
     build_output_stream,
+    select_host,
+    AnalysisSource,
     OutputBuffer,
     Widget as OutputWidget,
-    ControlMessage
+    ControlMessage,
+    MAX_TAPS,
+    SAMPLE_RATE,
+    SCOPE_BLOCKS,
+    SCOPE_RING_BLOCKS
 };
+use crate::config::{last_view_path, load_settings, view_preset_names, view_preset_path, Config};
+use crate::display::{parse_ms_to_samples, parse_percent, DisplayPreferences};
+use crate::record::{Widget as RecordWidget, MAX_CAPTURE_SECONDS};
+use crate::registry::{Constructor, Registry};
+use crate::report::{save_png, Report};
 use crate::analyze::{
+    bin_amplitude,
     build_window_function,
+    correlation,
+    decimate_to_columns,
+    estimate_delay,
+    estimate_frequency,
+    find_rising_zero_crossing,
+    fit_slope,
+    follow_envelope,
+    lap,
+    load_window_function,
+    magnitude_to_db,
+    mean_and_std_dev,
+    median,
+    noise_floor_db,
+    noise_reference_db,
+    scale_spectrum,
+    smooth_spectrum,
+    spectral_centroid_and_bandwidth,
+    total_harmonic_distortion,
+    transform_block,
+    weighting_gain,
+    CORRELATION_HISTORY,
+    DEFAULT_DB_FLOOR,
+    MAX_SWEEP_POINTS,
+    INVERSION_THRESHOLD,
+    MAX_ALIGNMENT_DELAY,
+    MAX_TRIGGER_HOLDOFF_MS,
+    FREQ_EST_HISTORY,
+    FREQ_LOCK_THRESHOLD_HZ,
+    TIMING_SECTIONS,
+    FrequencyEstimator,
+    GateMode,
+    NoiseReference,
     PlotView,
-    TimeSeriesTracking
+    SpectrumScaling,
+    Sweep,
+    TimeSeriesTracking,
+    Weighting,
+    WindowFunction
 };
 
 const BUFFER_SIZE: usize = 8192;
 const RINGBUFFER_CAPACITY: usize = 64;
+const STOP_TIMEOUT: Duration = Duration::from_millis(250);
+const TRACE_A_ID: &str = "TraceA";
+const TRACE_B_ID: &str = "TraceB";
+
+
+pub fn self_test() -> bool {
+    analyze::self_test::<BUFFER_SIZE>()
+}
 
 
 pub trait Module<const IN: usize, const OUT: usize>: 'static + Sized + Send {
     fn map_inputs(&mut self, input_buffer: &[f32; IN]);
     fn map_outputs(&mut self, output_buffer: &mut [f32; OUT]);
+
+    // Internal signals routed to the analyzer but never to the DAC, up to `MAX_TAPS`
+    fn debug_taps(&self) -> &[f32] {
+        &[]
+    }
+
+    fn debug_tap_names(&self) -> &[&'static str] {
+        &[]
+    }
     
     fn run(self) -> eframe::Result {
         let context: Context<IN, OUT, BUFFER_SIZE> = Context::new(self);
@@ -47,22 +123,109 @@ pub trait Module<const IN: usize, const OUT: usize>: 'static + Sized + Send {
 
 pub struct Context<const IN: usize, const OUT: usize, const SIZE: usize> {
     stream: Stream,
+    playing: Arc<AtomicBool>,
+    host_id: HostId,
     sender: Producer<ControlMessage>,
     receiver: Consumer<Event<IN>>,
     input_widget: InputWidget<IN>,
     output_widget: OutputWidget<OUT>,
-    output_buffer: Arc<Mutex<OutputBuffer<OUT, SIZE>>>,
-    output_buffer_time_series: [PlotPoint; SIZE],
+    record_widget: RecordWidget,
+    modules: Vec<(&'static str, Constructor<IN, OUT>)>,
+    tap_names: Vec<&'static str>,
+    selected_module: usize,
+    output_buffer: Arc<Mutex<OutputBuffer<IN, OUT, SIZE>>>,
+    output_buffer_time_series: Vec<PlotPoint>,
+    output_buffer_time_series_b: Vec<PlotPoint>,
+    output_buffer_envelope: Vec<PlotPoint>,
+    scope_span: usize,
+    envelope_attack: f32,
+    envelope_release: f32,
+    show_envelope: bool,
+    freeze_on_clip: bool,
+    scope_frozen: bool,
+    overlay_delay: usize,
+    align_pending: bool,
+    trigger_holdoff: f32,
+    trigger_holdoff_remaining: usize,
+    trigger_offset: usize,
+    trigger_scope_index: usize,
     output_buffer_freq_est: f32,
+    freq_estimator: FrequencyEstimator,
+    freq_est_history: [f32; FREQ_EST_HISTORY],
+    freq_est_history_index: usize,
+    median_filter: bool,
+    median_length: usize,
     output_buffer_phase: usize,
     output_spectrum_complex: [Complex32; SIZE],
     output_spectrum_magnitude: [PlotPoint; SIZE],
     output_spectrum_phase: [f32; SIZE],
     output_spectrum_filtered: [f64; SIZE],
+    output_spectrum_average: [f64; SIZE],
+    output_spectrum_complex_b: [Complex32; SIZE],
+    sweep_spectrum: [Complex32; SIZE],
+    output_spectrum_magnitude_b: [PlotPoint; SIZE],
+    output_spectrum_filtered_b: [f64; SIZE],
+    output_spectrum_average_b: [f64; SIZE],
+    output_spectrum_max: [PlotPoint; SIZE],
+    output_spectrum_max_age: [f32; SIZE],
+    show_max_hold: bool,
+    max_hold_time: f32,
+    max_hold_infinite: bool,
+    sweep: Sweep,
+    spectrum_markers: [Option<f64>; 2],
+    next_marker: usize,
+    slope_range: Option<(f64, f64)>,
+    spectrum_zoom: Option<(f64, f64)>,
+    zoom_octaves: f64,
+    spectrum_scaling: SpectrumScaling,
+    spectrum_reference: Option<[PlotPoint; SIZE]>,
+    show_reference_difference: bool,
+    noise_reference: NoiseReference,
+    db_floor: f64,
+    show_harmonics: bool,
+    harmonic_count: usize,
+    weighting: Weighting,
+    weighted_readouts: bool,
+    gate_enabled: bool,
+    gate_threshold: f64,
+    gate_mode: GateMode,
+    single_capture: bool,
+    single_capture_pending: bool,
+    smoothing_reset: bool,
+    smoothing_reset_threshold: f64,
+    averaging: bool,
+    average_frames: usize,
+    average_count: usize,
     fft_window_func: [f32; SIZE],
-    output_channel: usize,
+    window_function: WindowFunction,
+    window_path: String,
+    window_normalize: bool,
+    window_status: String,
+    fft: Arc<dyn Fft<f32>>,
+    output_channel: AnalysisSource,
+    output_channel_b: AnalysisSource,
+    mono_cancellation: Option<f64>,
+    correlation: Option<f32>,
+    correlation_history: [f32; CORRELATION_HISTORY],
+    correlation_history_index: usize,
+    spectral_centroid: Option<(f64, f64)>,
+    stereo: bool,
     plot_view: PlotView,
+    manual_bounds: Vec<PlotView>,
+    time_series_stroke: Stroke,
+    time_series_stroke_b: Stroke,
+    spectrum_stroke: Stroke,
     tracking: TimeSeriesTracking,
+    pause_on_menu: bool,
+    show_timing: bool,
+    timing: [f64; TIMING_SECTIONS.len()],
+    view_preset_name: String,
+    view_preset_names: Vec<String>,
+    view_preset_status: String,
+    report_status: String,
+    report_screenshot: Option<PathBuf>,
+    plot_rect: egui::Rect,
+    display: DisplayPreferences,
     running: bool
 }
 
@@ -71,107 +234,351 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> Context<IN, OUT, SIZE
     where
         M: 'static + Module<IN, OUT> + Send
     {
-        let (
-            message_sender,
-            message_receiver
-        ) = RingBuffer::new(RINGBUFFER_CAPACITY);
-
-        let (
-            event_sender,
-            event_receiver
-        ) = RingBuffer::new(RINGBUFFER_CAPACITY);
-
         let output_buffer = Arc::new(Mutex::new(
             OutputBuffer::new()
         ));
 
-        let stream = build_output_stream(
-            module,
-            message_receiver,
-            event_sender,
-            output_buffer.clone()
-        );
+        let host_id = select_host(load_settings().get("host")).id();
+        let playing = Arc::new(AtomicBool::new(false));
+        let tap_names = module.debug_tap_names().iter().take(MAX_TAPS).copied().collect();
+
+        let (
+            stream,
+            message_sender,
+            event_receiver,
+            history_receiver
+        ) = connect(host_id, module, output_buffer.clone(), playing.clone());
 
         let input_widget = InputWidget::new();
         
-        let mut output_buffer_plot = [PlotPoint::new(0.0, 0.0); SIZE];
-        for i in 0..SIZE {
-            output_buffer_plot[i].x = i as f64;
-        }
+        let output_buffer_plot = (0..(SIZE * SCOPE_BLOCKS))
+            .map(|i| PlotPoint::new(i as f64, 0.0))
+            .collect::<Vec<_>>();
 
         let mut output_spectrum_magnitude = [PlotPoint::new(0.0, 0.0); SIZE];
-        for i in 0..SIZE {
-            let f = (i + 1) as f64 / SIZE as f64;
-            output_spectrum_magnitude[i].x = f.log2();
+        for (i, point) in output_spectrum_magnitude.iter_mut().enumerate() {
+            // Bin i sits at i / SIZE cycles per sample.  DC (bin 0) maps to -inf and is not plotted.
+            let f = i as f64 / SIZE as f64;
+            point.x = f.log2();
         }
 
-        Context {
+        let mut context = Context {
             stream,
+            playing,
+            host_id,
             sender: message_sender,
             receiver: event_receiver,
             input_widget,
-            output_widget: OutputWidget::new(),
+            output_widget: OutputWidget::new(host_id),
+            record_widget: RecordWidget::new(history_receiver),
+            modules: Vec::new(),
+            tap_names,
+            selected_module: 0,
             output_buffer,
-            output_buffer_time_series: output_buffer_plot,
+            output_buffer_time_series: output_buffer_plot.clone(),
+            output_buffer_time_series_b: output_buffer_plot.clone(),
+            output_buffer_envelope: output_buffer_plot,
+            scope_span: 1,
+            envelope_attack: 1.0,
+            envelope_release: 50.0,
+            show_envelope: false,
+            freeze_on_clip: false,
+            scope_frozen: false,
+            overlay_delay: 0,
+            align_pending: false,
+            trigger_holdoff: 0.0,
+            trigger_holdoff_remaining: 0,
+            trigger_offset: 0,
+            trigger_scope_index: 0,
             output_buffer_freq_est: 0.0,
+            freq_estimator: FrequencyEstimator::default(),
+            freq_est_history: [0.0; FREQ_EST_HISTORY],
+            freq_est_history_index: 0,
+            median_filter: false,
+            median_length: 5,
             output_buffer_phase: 0,
             output_spectrum_complex: [Complex32::default(); SIZE],
             output_spectrum_magnitude,
             output_spectrum_phase: [0.0; SIZE],
             output_spectrum_filtered: [0.0; SIZE],
-            fft_window_func: build_window_function(),
-            output_channel: 0,
+            output_spectrum_average: [0.0; SIZE],
+            output_spectrum_complex_b: [Complex32::default(); SIZE],
+            sweep_spectrum: [Complex32::default(); SIZE],
+            output_spectrum_magnitude_b: output_spectrum_magnitude,
+            output_spectrum_filtered_b: [0.0; SIZE],
+            output_spectrum_average_b: [0.0; SIZE],
+            output_spectrum_max: output_spectrum_magnitude,
+            output_spectrum_max_age: [0.0; SIZE],
+            show_max_hold: false,
+            max_hold_time: 2.0,
+            max_hold_infinite: false,
+            sweep: Sweep::new(),
+            spectrum_markers: [None; 2],
+            next_marker: 0,
+            slope_range: None,
+            spectrum_zoom: None,
+            zoom_octaves: 1.0,
+            spectrum_scaling: SpectrumScaling::default(),
+            spectrum_reference: None,
+            show_reference_difference: false,
+            noise_reference: NoiseReference::default(),
+            db_floor: DEFAULT_DB_FLOOR,
+            show_harmonics: false,
+            harmonic_count: 5,
+            weighting: Weighting::default(),
+            weighted_readouts: false,
+            gate_enabled: false,
+            gate_threshold: -60.0,
+            gate_mode: GateMode::default(),
+            single_capture: false,
+            single_capture_pending: false,
+            smoothing_reset: true,
+            smoothing_reset_threshold: 0.5,
+            averaging: false,
+            average_frames: 16,
+            average_count: 0,
+            fft_window_func: build_window_function(WindowFunction::Hann),
+            window_function: WindowFunction::default(),
+            window_path: String::new(),
+            window_normalize: true,
+            window_status: String::new(),
+            fft: FftPlanner::new().plan_fft_forward(SIZE),
+            output_channel: AnalysisSource::Channel(0),
+            output_channel_b: AnalysisSource::Channel(OUT.min(2).saturating_sub(1)),
+            mono_cancellation: None,
+            correlation: None,
+            correlation_history: [0.0; CORRELATION_HISTORY],
+            correlation_history_index: 0,
+            spectral_centroid: None,
+            stereo: false,
             plot_view: PlotView::TimeSeries,
+            manual_bounds: Vec::new(),
+            time_series_stroke: Stroke::new(1.5, Color32::LIGHT_BLUE),
+            time_series_stroke_b: Stroke::new(1.5, Color32::ORANGE),
+            spectrum_stroke: Stroke::new(1.5, Color32::LIGHT_BLUE),
             tracking: TimeSeriesTracking::Static,
+            pause_on_menu: false,
+            show_timing: false,
+            timing: [0.0; TIMING_SECTIONS.len()],
+            view_preset_name: String::new(),
+            view_preset_names: view_preset_names(),
+            view_preset_status: String::new(),
+            report_status: String::new(),
+            report_screenshot: None,
+            plot_rect: egui::Rect::NOTHING,
+            display: DisplayPreferences::load(),
             running: true
+        };
+
+        // Pick up the analyzer where the last session left it
+        if let Ok(config) = last_view_path().and_then(|path| Config::load(&path)) {
+            context.apply_view_config(&config);
         }
+        context
+    }
+
+    fn from_registry(registry: Registry<IN, OUT>) -> Self {
+        let mut context = Context::new((registry.entries[0].1)());
+        context.modules = registry.entries;
+        context
+    }
+
+    // The old stream owns its channel state, so the controls start over with the new module
+    fn switch_module(&mut self, index: usize) {
+        self.stop_stream();
+
+        let module = (self.modules[index].1)();
+        self.tap_names = module.debug_tap_names().iter().take(MAX_TAPS).copied().collect();
+        if matches!(self.output_channel, AnalysisSource::Tap(_)) {
+            self.output_channel = AnalysisSource::Channel(0);
+        }
+        if matches!(self.output_channel_b, AnalysisSource::Tap(_)) {
+            self.output_channel_b = AnalysisSource::Channel(OUT.min(2).saturating_sub(1));
+        }
+
+        let (
+            stream,
+            message_sender,
+            event_receiver,
+            history_receiver
+        ) = connect(self.host_id, module, self.output_buffer.clone(), self.playing.clone());
+
+        self.stream = stream;
+        self.sender = message_sender;
+        self.receiver = event_receiver;
+        self.input_widget = InputWidget::new();
+        self.output_widget = OutputWidget::new(self.host_id);
+        self.record_widget = RecordWidget::new(history_receiver);
+        self.selected_module = index;
+
+        self.start_stream();
     }
 
     fn process_output_buffer(&mut self) {
         let mut output_buffer = self.output_buffer.lock().unwrap();
+        let mut clock = self.show_timing.then(Instant::now);
         
         // Process Spectrum
         let start = output_buffer.index;
-        for i in 0..SIZE {
-            self.output_spectrum_complex[i] = Complex32 {
-                re: self.fft_window_func[i] * output_buffer.buffer[self.output_channel][(start + i) % SIZE],
-                im: 0.0
-            };
-        }
 
-        let fft  = Radix4::new(
-            SIZE,
-            FftDirection::Forward
-        );
+        // Gate analysis on silence so noise isn't normalized up to full scale
+        let rms = (
+            (0..SIZE)
+                .map(|i| output_buffer.source_buffer(self.output_channel)[i].powi(2))
+                .sum::<f32>() / SIZE as f32
+        ).sqrt();
+        let gated = self.gate_enabled && magnitude_to_db(rms as f64, self.db_floor) < self.gate_threshold;
 
-        fft.process(&mut self.output_spectrum_complex);
+        // A single capture analyzes one block unsmoothed, then holds it
+        let frozen = (gated && self.gate_mode == GateMode::Freeze)
+            || (self.single_capture && !self.single_capture_pending);
+        // Linear averaging takes the running mean of the first N frames, then keeps a 1/N weight
+        let mut smoothing = if self.single_capture {
+            1.0
+        } else if self.averaging {
+            1.0 / (self.average_count + 1).min(self.average_frames) as f64
+        } else {
+            0.5
+        };
+        self.single_capture_pending = false;
 
-        let mut max_norm = 0.0;
         let mut max_norm_index = 0;
         let mut max_norm_phase_diff = 0.0;
-        for i in 0..SIZE {
-            let (norm, phase) = self.output_spectrum_complex[i].to_polar();
-            //let norm_unfiltered = 20.0 * (r as f64).log10();
-            let norm_unfiltered = norm as f64;
-            
-            self.output_spectrum_filtered[i] += 0.5 * (norm_unfiltered - self.output_spectrum_filtered[i]);
-            let norm_filtered = self.output_spectrum_filtered[i];
-            
-            self.output_spectrum_magnitude[i].y = norm_filtered;
+        if !frozen {
+            let source = output_buffer.source_buffer(self.output_channel);
+            transform_block(
+                &*self.fft,
+                &self.fft_window_func,
+                |i| source[(start + i) % SIZE],
+                &mut self.output_spectrum_complex
+            );
+            lap(&mut self.timing[0], &mut clock);
+
+            // Snap the smoother to the new frame when the spectrum changes abruptly
+            if self.smoothing_reset {
+                let (delta, total) = (0..SIZE).fold((0.0, 0.0), |(delta, total), i| {
+                    let filtered = self.output_spectrum_filtered[i];
+                    (
+                        delta + (self.output_spectrum_complex[i].norm() as f64 - filtered).abs(),
+                        total + filtered
+                    )
+                });
+                if total > 0.0 && delta / total > self.smoothing_reset_threshold {
+                    smoothing = 1.0;
+                    self.average_count = 0;
+                }
+            }
+            self.average_count = (self.average_count + 1).min(self.average_frames);
+
+            (max_norm_index, max_norm_phase_diff) = smooth_spectrum(
+                &self.output_spectrum_complex,
+                &mut self.output_spectrum_filtered,
+                &mut self.output_spectrum_phase,
+                smoothing
+            );
+
+            let mut max_magnitude = 0.0;
+            for i in 0..SIZE {
+                let hz = i.min(SIZE - i) as f32 / SIZE as f32 * SAMPLE_RATE as f32;
+                let magnitude = self.output_spectrum_filtered[i] * weighting_gain(self.weighting, hz) as f64;
+                self.output_spectrum_magnitude[i].y = magnitude;
+                if magnitude > max_magnitude {
+                    max_magnitude = magnitude;
+                }
+            }
+
+            lap(&mut self.timing[1], &mut clock);
+
+            let window_sum: f32 = self.fft_window_func.iter().sum();
+            scale_spectrum(
+                self.spectrum_scaling,
+                &mut self.output_spectrum_magnitude,
+                &mut self.output_spectrum_average,
+                max_magnitude,
+                window_sum
+            );
+
+            // Process second channel in stereo mode
+            if self.stereo {
+                let source = output_buffer.source_buffer(self.output_channel_b);
+                transform_block(
+                    &*self.fft,
+                    &self.fft_window_func,
+                    |i| source[(start + i) % SIZE],
+                    &mut self.output_spectrum_complex_b
+                );
+
+                let mut max_magnitude_b = 0.0;
+                for i in 0..SIZE {
+                    let norm_unfiltered = self.output_spectrum_complex_b[i].norm() as f64;
+                    self.output_spectrum_filtered_b[i] += smoothing * (norm_unfiltered - self.output_spectrum_filtered_b[i]);
+
+                    let hz = i.min(SIZE - i) as f32 / SIZE as f32 * SAMPLE_RATE as f32;
+                    let magnitude = self.output_spectrum_filtered_b[i] * weighting_gain(self.weighting, hz) as f64;
+                    self.output_spectrum_magnitude_b[i].y = magnitude;
+                    if magnitude > max_magnitude_b {
+                        max_magnitude_b = magnitude;
+                    }
+                }
+
+                scale_spectrum(
+                    self.spectrum_scaling,
+                    &mut self.output_spectrum_magnitude_b,
+                    &mut self.output_spectrum_average_b,
+                    max_magnitude_b,
+                    window_sum
+                );
+            }
+        }
 
-            let prev_phase = self.output_spectrum_phase[i];
-            let phase_diff = phase - prev_phase;
-            self.output_spectrum_phase[i] = phase;
+        lap(&mut self.timing[2], &mut clock);
 
-            if norm_filtered > max_norm {
-                max_norm = norm_filtered;
-                max_norm_index = i;
-                max_norm_phase_diff = phase_diff;
+        if gated && self.gate_mode == GateMode::Blank {
+            for i in 0..SIZE {
+                self.output_spectrum_magnitude[i].y = 0.0;
+                self.output_spectrum_magnitude_b[i].y = 0.0;
             }
         }
 
-        for i in 0..SIZE {
-            self.output_spectrum_magnitude[i].y /= max_norm;
+        // A held bin is replaced once it's exceeded or has been held long enough
+        if self.show_max_hold && !frozen {
+            let elapsed = output_buffer.counter as f32 / SAMPLE_RATE as f32;
+            for i in 0..SIZE {
+                let magnitude = self.output_spectrum_magnitude[i].y;
+                let expired = !self.max_hold_infinite && self.output_spectrum_max_age[i] >= self.max_hold_time;
+                if magnitude >= self.output_spectrum_max[i].y || expired {
+                    self.output_spectrum_max[i].y = magnitude;
+                    self.output_spectrum_max_age[i] = 0.0;
+                } else {
+                    self.output_spectrum_max_age[i] += elapsed;
+                }
+            }
+        }
+
+
+        // Unscaled bins, so the plot's scaling and weighting don't move the centroid
+        self.spectral_centroid = match gated && self.gate_mode == GateMode::Blank {
+            true => None,
+            false => spectral_centroid_and_bandwidth(&self.output_spectrum_filtered)
+        };
+
+        // Compare the mono sum against the individual sides to expose phase cancellation
+        self.mono_cancellation = if self.output_channel == AnalysisSource::Mono
+            || (self.stereo && self.output_channel_b == AnalysisSource::Mono)
+        {
+            let rms = |buffer: &[f32]| (
+                buffer.iter().map(|x| x.powi(2)).sum::<f32>() / SIZE as f32
+            ).sqrt() as f64;
+            let sides = rms(&output_buffer.master[0]) + rms(&output_buffer.master[1]);
+            (sides > 0.0).then(|| 20.0 * (rms(&output_buffer.mono) / sides).log10())
+        } else {
+            None
+        };
+
+        self.correlation = correlation(&output_buffer.master[0], &output_buffer.master[1]);
+        if let Some(correlation) = self.correlation {
+            self.correlation_history[self.correlation_history_index] = correlation;
+            self.correlation_history_index = (self.correlation_history_index + 1) % CORRELATION_HISTORY;
         }
 
 
@@ -180,23 +587,25 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> Context<IN, OUT, SIZE
         let dt = output_buffer.counter as f32;
         output_buffer.counter = 0;
         
-        if dt != 0.0 {
-            let freq_est = max_norm_index as f32 / SIZE as f32;
-            let dp = max_norm_phase_diff;
-            let mut phase = 0.0;
-            let mut freq_prev = 0.0;
-            self.output_buffer_freq_est = loop {
-                let freq = (dp + phase) / (2.0 * PI * dt);
-                if freq > freq_est {
-                    if freq - freq_est < freq_est - freq_prev {
-                        break freq;
-                    } else {
-                        break freq_prev;
-                    };
-                }
-                freq_prev = freq;
-                phase += 2.0 * PI;
-            };
+        if dt != 0.0 && !gated && !frozen {
+            self.output_buffer_freq_est = estimate_frequency(
+                self.freq_estimator,
+                max_norm_index,
+                max_norm_phase_diff,
+                dt,
+                SIZE
+            );
+
+            self.freq_est_history[self.freq_est_history_index] = self.output_buffer_freq_est;
+            self.freq_est_history_index = (self.freq_est_history_index + 1) % FREQ_EST_HISTORY;
+
+            // The history keeps raw estimates; only the displayed and tracked value is filtered
+            if self.median_filter {
+                let recent = (1..=self.median_length)
+                    .map(|k| self.freq_est_history[(self.freq_est_history_index + FREQ_EST_HISTORY - k) % FREQ_EST_HISTORY])
+                    .collect::<Vec<_>>();
+                self.output_buffer_freq_est = median(&recent);
+            }
         }
 
         if self.output_buffer_freq_est > 0.0 {
@@ -205,20 +614,401 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> Context<IN, OUT, SIZE
                 + (1.0 / self.output_buffer_freq_est).round() as usize
             ) % SIZE;
         }
+        lap(&mut self.timing[3], &mut clock);
+
+        // The sweep measures every processed block from its own unsmoothed transform, so holds,
+        // single captures and averaging can't leave stale or lagging points; each step waits for
+        // two full blocks of the new tone
+        if self.sweep.running() {
+            let source = output_buffer.source_buffer(self.output_channel);
+            transform_block(
+                &*self.fft,
+                &self.fft_window_func,
+                |i| source[(start + i) % SIZE],
+                &mut self.sweep_spectrum
+            );
+            let spectrum = &self.sweep_spectrum;
+            let window = &self.fft_window_func;
+            let next = self.sweep.update(dt as usize, 2 * SIZE, |frequency| {
+                let bin = (frequency * SIZE as f32).round() as usize;
+                (bin.saturating_sub(2).max(1)..=(bin + 2).min(SIZE / 2))
+                    .map(|i| bin_amplitude(window, spectrum[i].norm() as f64))
+                    .fold(0.0, f64::max)
+            });
+            if let Some(frequency) = next {
+                self.sender.push(ControlMessage::InputControl {
+                    channel: self.sweep.channel,
+                    command: InputCommand::SetFrequency(frequency)
+                }).ok();
+            }
+        }
+
+        let clipped = std::mem::take(&mut output_buffer.clipped);
+        self.record_widget.add_dropped(std::mem::take(&mut output_buffer.history_dropped));
+        if self.scope_frozen {
+            return;
+        }
         
+        // The scope ring advances with the analysis ring, so positions agree modulo SIZE
+        let scope_len = SIZE * SCOPE_RING_BLOCKS;
+        let span = self.scope_span * SIZE;
+        let newest = (output_buffer.scope_index + scope_len - span) % scope_len;
         let offset = match self.tracking {
-            TimeSeriesTracking::Static => start,
-            TimeSeriesTracking::Following => self.output_buffer_phase,
+            TimeSeriesTracking::Static => newest,
+            TimeSeriesTracking::Following => {
+                let delta = (newest + SIZE - self.output_buffer_phase) % SIZE;
+                (newest + scope_len - delta) % scope_len
+            },
+            TimeSeriesTracking::ZeroCrossing => {
+                // Samples written since the last frame count down the holdoff; until it expires the
+                // previous trigger is kept, as long as its span has not been overwritten yet
+                let elapsed = (output_buffer.scope_index + scope_len - self.trigger_scope_index) % scope_len;
+                self.trigger_scope_index = output_buffer.scope_index;
+                self.trigger_holdoff_remaining = self.trigger_holdoff_remaining.saturating_sub(elapsed);
+                let age = (newest + scope_len - self.trigger_offset) % scope_len;
+                if self.trigger_holdoff_remaining > 0 && age <= scope_len - span {
+                    self.trigger_offset
+                } else {
+                    // Only look back as far as the ring still holds a full span
+                    let room = (scope_len - span).min(SIZE);
+                    self.trigger_offset = find_rising_zero_crossing(
+                        output_buffer.source_scope(self.output_channel),
+                        newest,
+                        room
+                    ).unwrap_or(newest);
+                    self.trigger_holdoff_remaining = (self.trigger_holdoff * SAMPLE_RATE as f32 / 1000.0) as usize;
+                    self.trigger_offset
+                }
+            }
         };
 
-        for i in 0..SIZE {
+        for i in 0..span {
             self.output_buffer_time_series[i].y = 
-                output_buffer.buffer[self.output_channel][(offset + i) % SIZE] as f64;
+                output_buffer.source_scope(self.output_channel)[(offset + i) % scope_len] as f64;
+        }
+
+        // Copy the newest block of A and the input overlay for latency estimation; the
+        // correlation itself runs after the lock is released
+        let overlay = self.stereo && matches!(self.output_channel_b, AnalysisSource::Input(_));
+        let alignment = (overlay && std::mem::take(&mut self.align_pending)).then(|| {
+            let start = (output_buffer.scope_index + scope_len - SIZE) % scope_len;
+            let output = (0..SIZE)
+                .map(|i| output_buffer.source_scope(self.output_channel)[(start + i) % scope_len])
+                .collect::<Vec<_>>();
+            let input = (0..(SIZE + MAX_ALIGNMENT_DELAY))
+                .map(|i| output_buffer.source_scope(self.output_channel_b)[(start + scope_len - MAX_ALIGNMENT_DELAY + i) % scope_len])
+                .collect::<Vec<_>>();
+            (output, input)
+        });
+
+        if self.stereo {
+            // Only the input overlay is delayed, and never further back than the scope ring holds
+            // once the trigger or phase tracking has already stepped back from the newest span
+            let back = (newest + scope_len - offset) % scope_len;
+            let delay = match overlay {
+                true => self.overlay_delay.min(scope_len - span - back),
+                false => 0
+            };
+            for i in 0..span {
+                self.output_buffer_time_series_b[i].y =
+                    output_buffer.source_scope(self.output_channel_b)[(offset + scope_len - delay + i) % scope_len] as f64;
+            }
+        }
+        drop(output_buffer);
+
+        if let Some((output, input)) = alignment {
+            self.overlay_delay = estimate_delay(&output, &input, MAX_ALIGNMENT_DELAY);
+        }
+
+        if self.show_envelope {
+            follow_envelope(
+                &self.output_buffer_time_series[..span],
+                &mut self.output_buffer_envelope[..span],
+                self.envelope_attack,
+                self.envelope_release
+            );
+        }
+        lap(&mut self.timing[4], &mut clock);
+
+        // The block holding the clip has just been drawn, so stop here
+        if clipped && self.freeze_on_clip {
+            self.scope_frozen = true;
         }
     }
 
-    fn run(self) -> eframe::Result {
+    // Absolute bin amplitude (a full scale sine reads 1.0), independent of the plot scaling;
+    // the display weighting only applies when the user opts in
+    fn readout_level(&self, bin: usize) -> f64 {
+        let weight = match self.weighted_readouts {
+            true => {
+                let hz = bin.min(SIZE - bin) as f32 / SIZE as f32 * SAMPLE_RATE as f32;
+                weighting_gain(self.weighting, hz) as f64
+            },
+            false => 1.0
+        };
+        bin_amplitude(&self.fft_window_func, weight * self.output_spectrum_filtered[bin])
+    }
+
+    // Markers sit on the log2 frequency axis; read back Hz and the nearest bin's level in dBFS
+    fn marker_readout(&self, x: f64) -> (f64, f64) {
+        let bin = ((x.exp2() * SIZE as f64).round() as usize).clamp(1, SIZE / 2);
+        (
+            x.exp2() * SAMPLE_RATE as f64,
+            magnitude_to_db(self.readout_level(bin), self.db_floor)
+        )
+    }
+
+    fn view_config(&self) -> Config {
+        let mut config = Config::new();
+        config.set("plot_view", self.plot_view);
+        config.set("tracking", self.tracking);
+        config.set("scope_span", self.scope_span);
+        config.set("show_envelope", self.show_envelope);
+        config.set("envelope_attack", self.envelope_attack);
+        config.set("envelope_release", self.envelope_release);
+        config.set("freq_estimator", self.freq_estimator);
+        config.set("median_filter", self.median_filter);
+        config.set("median_length", self.median_length);
+        config.set("stereo", self.stereo);
+        config.set("overlay_delay", self.overlay_delay);
+        config.set("trigger_holdoff", self.trigger_holdoff);
+        config.set("spectrum_scaling", self.spectrum_scaling);
+        config.set("weighting", self.weighting);
+        config.set("weighted_readouts", self.weighted_readouts);
+        config.set("db_floor", self.db_floor);
+        config.set("noise_reference", self.noise_reference);
+        config.set("smoothing_reset", self.smoothing_reset);
+        config.set("smoothing_reset_threshold", self.smoothing_reset_threshold);
+        config.set("averaging", self.averaging);
+        config.set("average_frames", self.average_frames);
+        config.set("gate_enabled", self.gate_enabled);
+        config.set("gate_threshold", self.gate_threshold);
+        config.set("gate_mode", self.gate_mode);
+        config.set("show_harmonics", self.show_harmonics);
+        config.set("harmonic_count", self.harmonic_count);
+        config.set("show_max_hold", self.show_max_hold);
+        config.set("max_hold_time", self.max_hold_time);
+        config.set("max_hold_infinite", self.max_hold_infinite);
+        config.set("zoom_octaves", self.zoom_octaves);
+        if let Some((min_x, max_x)) = self.spectrum_zoom {
+            config.set("zoom_min", min_x);
+            config.set("zoom_max", max_x);
+        }
+        config.set("window", self.window_function);
+        match self.window_function {
+            WindowFunction::Kaiser { beta } => config.set("window_beta", beta),
+            WindowFunction::Gaussian { sigma } => config.set("window_sigma", sigma),
+            _ => {}
+        }
+        config.set("window_path", &self.window_path);
+        config.set("window_normalize", self.window_normalize);
+        config
+    }
+
+    // Missing or unparseable keys leave the current setting alone.  The FFT size is fixed at
+    // compile time, so there are no arrays to reallocate here.
+    fn apply_view_config(&mut self, config: &Config) {
+        macro_rules! restore {
+            ($field:ident, $method:ident) => {
+                if let Some(value) = config.$method(stringify!($field)) {
+                    self.$field = value;
+                }
+            };
+        }
+        restore!(plot_view, parse_enum);
+        restore!(tracking, parse_enum);
+        restore!(scope_span, parse);
+        self.scope_span = self.scope_span.clamp(1, SCOPE_BLOCKS);
+        restore!(show_envelope, parse);
+        restore!(envelope_attack, parse);
+        restore!(envelope_release, parse);
+        restore!(freq_estimator, parse_enum);
+        restore!(median_filter, parse);
+        restore!(median_length, parse);
+        self.median_length = self.median_length.clamp(3, FREQ_EST_HISTORY);
+        restore!(stereo, parse);
+        restore!(overlay_delay, parse);
+        self.overlay_delay = self.overlay_delay.min(MAX_ALIGNMENT_DELAY);
+        restore!(trigger_holdoff, parse);
+        self.trigger_holdoff = self.trigger_holdoff.clamp(0.0, MAX_TRIGGER_HOLDOFF_MS);
+        restore!(spectrum_scaling, parse_enum);
+        restore!(weighting, parse_enum);
+        restore!(weighted_readouts, parse);
+        restore!(db_floor, parse);
+        restore!(noise_reference, parse_enum);
+        restore!(smoothing_reset, parse);
+        restore!(smoothing_reset_threshold, parse);
+        restore!(averaging, parse);
+        restore!(average_frames, parse);
+        self.average_frames = self.average_frames.max(2);
+        self.average_count = 0;
+        restore!(gate_enabled, parse);
+        restore!(gate_threshold, parse);
+        restore!(gate_mode, parse_enum);
+        restore!(show_harmonics, parse);
+        restore!(harmonic_count, parse);
+        restore!(show_max_hold, parse);
+        restore!(max_hold_time, parse);
+        restore!(max_hold_infinite, parse);
+        restore!(zoom_octaves, parse);
+        self.spectrum_zoom = config.parse("zoom_min").zip(config.parse("zoom_max"));
+        restore!(window_path, parse);
+        restore!(window_normalize, parse);
+
+        let window = config.get("window");
+        if window == Some("Custom") {
+            match load_window_function(&self.window_path, self.window_normalize) {
+                Ok(window) => {
+                    self.fft_window_func = window;
+                    self.window_function = WindowFunction::Custom;
+                },
+                Err(err) => self.window_status = err
+            }
+        } else if let Some(mut window_function) = WindowFunction::options().into_iter()
+            .find(|option| Some(option.to_string().as_str()) == window)
+        {
+            match &mut window_function {
+                WindowFunction::Kaiser { beta } => *beta = config.parse("window_beta").unwrap_or(*beta),
+                WindowFunction::Gaussian { sigma } => *sigma = config.parse("window_sigma").unwrap_or(*sigma),
+                _ => {}
+            }
+            self.window_function = window_function;
+            self.fft_window_func = build_window_function(window_function);
+        }
+
+        self.reset_max_hold();
+    }
+
+    fn source_name(&self, source: AnalysisSource) -> String {
+        match source {
+            AnalysisSource::Tap(i) => self.tap_names[i].to_string(),
+            other => other.to_string()
+        }
+    }
+
+    fn reset_max_hold(&mut self) {
+        for i in 0..SIZE {
+            self.output_spectrum_max[i].y = 0.0;
+            self.output_spectrum_max_age[i] = 0.0;
+        }
+    }
+
+    fn set_sweep_frequency(&mut self, frequency: f32) {
+        self.sender.push(ControlMessage::InputControl {
+            channel: self.sweep.channel,
+            command: InputCommand::SetFrequency(frequency)
+        }).ok();
+    }
+
+    // Levels are absolute dBFS from readout_level, whatever the plot's scaling; the display
+    // weighting is only included when readout weighting is enabled
+    fn report(&self) -> Report {
+        let levels = (0..SIZE)
+            .map(|i| PlotPoint::new(self.output_spectrum_magnitude[i].x, self.readout_level(i)))
+            .collect::<Vec<_>>();
+        let spectrum = &levels[1..=(SIZE / 2)];
+        let fundamental = self.output_buffer_freq_est;
+        let mut readout_config = Config::new();
+        readout_config.set("level_reference", "dBFS");
+        readout_config.set("weighting", match self.weighted_readouts {
+            true => self.weighting,
+            false => Weighting::Flat
+        });
+        readout_config.set("display_weighting", self.weighting);
+        readout_config.set("display_scaling", self.spectrum_scaling);
+        Report {
+            readouts: vec![
+                ("fundamental_hz", (fundamental > 0.0).then_some((fundamental * SAMPLE_RATE as f32) as f64)),
+                ("thd_percent", total_harmonic_distortion(&levels, fundamental, self.harmonic_count.max(2))
+                    .map(|thd| 100.0 * thd)),
+                ("noise_floor_db", Some(noise_floor_db(spectrum, self.db_floor))),
+                ("centroid_hz", self.spectral_centroid.map(|(centroid, _)| centroid)),
+                ("bandwidth_hz", self.spectral_centroid.map(|(_, bandwidth)| bandwidth)),
+                ("correlation", self.correlation.map(|c| c as f64))
+            ],
+            settings: vec![
+                ("readout_levels", readout_config),
+                ("analysis", self.view_config()),
+                ("inputs", self.input_widget.settings()),
+                ("outputs", self.output_widget.settings())
+            ],
+            spectrum: spectrum.iter()
+                .map(|point| (point.x.exp2() * SAMPLE_RATE as f64, magnitude_to_db(point.y, self.db_floor)))
+                .collect(),
+            response: self.sweep.response.iter()
+                .map(|point| (point.x.exp2() * SAMPLE_RATE as f64, magnitude_to_db(point.y, self.db_floor)))
+                .collect()
+        }
+    }
+
+    fn render_view_presets(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Views");
+        egui::Grid::new("ViewPresets")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Name:");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.view_preset_name);
+                    if ui.add_enabled(
+                        !self.view_preset_name.trim().is_empty(),
+                        egui::Button::new("Save")
+                    ).clicked() {
+                        let name = self.view_preset_name.trim().to_string();
+                        self.view_preset_status = match view_preset_path(&name).and_then(|path| self.view_config().save(&path)) {
+                            Ok(()) => format!("Saved {}", name),
+                            Err(err) => format!("Error: {}", err)
+                        };
+                        self.view_preset_names = view_preset_names();
+                    }
+                });
+
+                ui.end_row();
+
+                ui.label("Recall:");
+                let mut recalled = None;
+                egui::ComboBox::from_id_salt("ViewPresetSelect")
+                    .selected_text("Select…")
+                    .show_ui(ui, |ui| {
+                        for name in &self.view_preset_names {
+                            if ui.selectable_label(false, name).clicked() {
+                                recalled = Some(name.clone());
+                            }
+                        }
+                    });
+                if let Some(name) = recalled {
+                    self.view_preset_status = match view_preset_path(&name).and_then(|path| Config::load(&path)) {
+                        Ok(config) => {
+                            self.apply_view_config(&config);
+                            self.view_preset_name = name.clone();
+                            format!("Loaded {}", name)
+                        },
+                        Err(err) => format!("Error: {}", err)
+                    };
+                }
+
+                ui.end_row();
+            });
+        ui.label(&self.view_preset_status);
+    }
+
+    fn start_stream(&self) {
+        self.playing.store(true, Ordering::Relaxed);
         self.stream.play().unwrap();
+    }
+
+    // Wait for the callback to report that the fade has reached silence before it stops being
+    // called; a stalled device gives up after the timeout rather than hanging the UI
+    fn stop_stream(&self) {
+        self.playing.store(false, Ordering::Relaxed);
+        let deadline = Instant::now() + STOP_TIMEOUT;
+        while !self.output_buffer.lock().unwrap().faded_out && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        self.stream.pause().ok();
+    }
+
+    fn run(self) -> eframe::Result {
+        self.start_stream();
         
         let options = eframe::NativeOptions {
             viewport: egui::ViewportBuilder::default().with_inner_size([1024.0, 700.0]),
@@ -227,39 +1017,215 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> Context<IN, OUT, SIZE
         eframe::run_native(
             "DSP Test",
             options,
-            Box::new(|_cc| {
+            Box::new(|cc| {
+                cc.egui_ctx.set_theme(self.display.theme);
                 Ok(Box::new(self))
             }),
         )
     }
 }
 
+// Default bounds apply until the user pans or zooms a view, which then keeps its bounds until reset
+fn apply_default_bounds(
+    plot_ui: &mut PlotUi,
+    manual_bounds: &mut Vec<PlotView>,
+    view: PlotView,
+    bounds: PlotBounds,
+    drag_pans: bool
+) {
+    let response = plot_ui.response();
+    let scrolled = response.hovered() && plot_ui.ctx().input(|input| {
+        input.smooth_scroll_delta != egui::Vec2::ZERO || input.zoom_delta() != 1.0
+    });
+    if ((drag_pans && response.dragged()) || scrolled) && !manual_bounds.contains(&view) {
+        manual_bounds.push(view);
+    }
+
+    if !manual_bounds.contains(&view) {
+        plot_ui.set_plot_bounds(bounds);
+        plot_ui.set_auto_bounds(Vec2b::new(false, false));
+    }
+}
+
+fn connect<M, const IN: usize, const OUT: usize, const SIZE: usize>(
+    host_id: HostId,
+    module: M,
+    output_buffer: Arc<Mutex<OutputBuffer<IN, OUT, SIZE>>>,
+    playing: Arc<AtomicBool>
+) -> (Stream, Producer<ControlMessage>, Consumer<Event<IN>>, Consumer<[f32; 2]>)
+where
+    M: 'static + Module<IN, OUT> + Send
+{
+    let (
+        message_sender,
+        message_receiver
+    ) = RingBuffer::new(RINGBUFFER_CAPACITY);
+
+    let (
+        event_sender,
+        event_receiver
+    ) = RingBuffer::new(RINGBUFFER_CAPACITY);
+
+    let (
+        history_sender,
+        history_receiver
+    ) = RingBuffer::new(MAX_CAPTURE_SECONDS * SAMPLE_RATE);
+
+    let stream = build_output_stream(
+        &cpal::host_from_id(host_id).unwrap(),
+        module,
+        message_receiver,
+        event_sender,
+        history_sender,
+        output_buffer,
+        playing
+    );
+
+    (stream, message_sender, event_receiver, history_receiver)
+}
+
+impl<const IN: usize, const OUT: usize, const SIZE: usize> Drop for Context<IN, OUT, SIZE> {
+    fn drop(&mut self) {
+        // Stop the audio callback before the stream and its buffers are torn down.
+        self.stop_stream();
+        if let Ok(path) = last_view_path() {
+            let _ = self.view_config().save(&path);
+        }
+    }
+}
+
 impl<const IN: usize, const OUT: usize, const SIZE: usize> eframe::App for Context<IN, OUT, SIZE> {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         while let Ok(Event::State(input_channels)) = self.receiver.pop() {
             self.input_widget.set_models(input_channels);
         }
 
-        self.process_output_buffer();
+        let screenshot = ctx.input(|input| input.raw.events.iter().find_map(|event| match event {
+            egui::Event::Screenshot { image, .. } => Some(image.clone()),
+            _ => None
+        }));
+        if let Some(image) = screenshot
+            && let Some(path) = self.report_screenshot.take()
+        {
+            let plot = image.region(&self.plot_rect, Some(ctx.pixels_per_point()));
+            self.report_status = match save_png(&path, &plot) {
+                Ok(()) => format!("{}, {}", self.report_status, path.display()),
+                Err(err) => format!("Error: {}", err)
+            };
+        }
+
+        // Holding the last analysis while a popup is open keeps menus from stuttering
+        if !(self.pause_on_menu && ctx.memory(|memory| memory.any_popup_open())) {
+            self.process_output_buffer();
+        }
+        self.record_widget.update();
+
+        if !ctx.wants_keyboard_input() {
+            ctx.input(|input| {
+                if input.key_pressed(egui::Key::Num1) {
+                    self.plot_view = PlotView::TimeSeries;
+                }
+                if input.key_pressed(egui::Key::Num2) {
+                    self.plot_view = PlotView::Spectrum;
+                }
+                if input.key_pressed(egui::Key::Num3) {
+                    self.plot_view = PlotView::Window;
+                }
+                if input.key_pressed(egui::Key::Num4) {
+                    self.plot_view = PlotView::Complex;
+                }
+                if input.key_pressed(egui::Key::T) {
+                    self.tracking = match self.tracking {
+                        TimeSeriesTracking::Static => TimeSeriesTracking::Following,
+                        TimeSeriesTracking::Following => TimeSeriesTracking::ZeroCrossing,
+                        TimeSeriesTracking::ZeroCrossing => TimeSeriesTracking::Static
+                    };
+                }
+            });
+        }
 
         egui::SidePanel::left("Controls")
             .resizable(false)
             .show(ctx, |ui| {
-                self.input_widget.render(ui, &mut self.sender);
-                self.output_widget.render(ui, &mut self.sender);
+                self.input_widget.render(ui, &mut self.sender, &self.display);
+                self.output_widget.render(ui, &mut self.sender, &self.display);
+                ui.separator();
+                self.record_widget.render(ui);
+                ui.separator();
+                self.display.render(ui);
+                ui.separator();
+                self.render_view_presets(ui);
+                ui.separator();
+                if ui.button("Export Report").clicked() {
+                    self.report_status = match self.report().save() {
+                        Ok(path) => {
+                            // The plot image arrives with a later frame's events
+                            self.report_screenshot = Some(path.with_extension("png"));
+                            ctx.send_viewport_cmd(egui::ViewportCommand::Screenshot(egui::UserData::default()));
+                            format!("Saved {}", path.display())
+                        },
+                        Err(err) => format!("Error: {}", err)
+                    };
+                }
+                ui.label(&self.report_status);
             });
 
+        let mut next_module = None;
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
+                if !self.modules.is_empty() {
+                    ui.label("Module:");
+                    egui::ComboBox::from_id_salt("ModuleSelect")
+                        .selected_text(self.modules[self.selected_module].0)
+                        .show_ui(ui, |ui| {
+                            for (i, (name, _)) in self.modules.iter().enumerate() {
+                                if ui.selectable_label(self.selected_module == i, *name).clicked()
+                                    && self.selected_module != i
+                                {
+                                    next_module = Some(i);
+                                }
+                            }
+                        });
+
+                    ui.separator();
+                }
+
                 ui.label("Output Channel:");
                 egui::ComboBox::from_id_salt("OutputSelect")
-                    .selected_text(format!("{:?}", self.output_channel))
+                    .selected_text(self.source_name(self.output_channel))
                     .show_ui(ui, |ui| {
                         for i in 0..OUT {
-                            ui.selectable_value(&mut self.output_channel, i, i.to_string());
+                            ui.selectable_value(&mut self.output_channel, AnalysisSource::Channel(i), i.to_string());
+                        }
+                        ui.selectable_value(&mut self.output_channel, AnalysisSource::Mono, "Mono")
+                            .on_hover_text("Sum of the final L and R outputs");
+                        for (i, name) in self.tap_names.iter().enumerate() {
+                            ui.selectable_value(&mut self.output_channel, AnalysisSource::Tap(i), *name);
+                        }
+                        for i in 0..IN {
+                            ui.selectable_value(&mut self.output_channel, AnalysisSource::Input(i), format!("In {}", i))
+                                .on_hover_text("Signal feeding the module, after the input mixer");
                         }
                     });
 
+                ui.checkbox(&mut self.stereo, "Stereo");
+                ui.add_enabled_ui(self.stereo, |ui| {
+                    egui::ComboBox::from_id_salt("OutputSelectB")
+                        .selected_text(self.source_name(self.output_channel_b))
+                        .show_ui(ui, |ui| {
+                            for i in 0..OUT {
+                                ui.selectable_value(&mut self.output_channel_b, AnalysisSource::Channel(i), i.to_string());
+                            }
+                            ui.selectable_value(&mut self.output_channel_b, AnalysisSource::Mono, "Mono");
+                            for (i, name) in self.tap_names.iter().enumerate() {
+                                ui.selectable_value(&mut self.output_channel_b, AnalysisSource::Tap(i), *name);
+                            }
+                            for i in 0..IN {
+                                ui.selectable_value(&mut self.output_channel_b, AnalysisSource::Input(i), format!("In {}", i));
+                            }
+                        });
+                });
+
                 ui.separator();
 
 
@@ -270,7 +1236,7 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> eframe::App for Conte
                         self.plot_view == PlotView::TimeSeries,
                         "Time Series"
                     )
-                ).clicked() {
+                ).on_hover_text("1").clicked() {
                     self.plot_view = PlotView::TimeSeries;
                 }
 
@@ -279,7 +1245,7 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> eframe::App for Conte
                         self.plot_view == PlotView::Spectrum,
                         "Spectrum"
                     )
-                ).clicked() {
+                ).on_hover_text("2").clicked() {
                     self.plot_view = PlotView::Spectrum;
                 }
 
@@ -288,10 +1254,26 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> eframe::App for Conte
                         self.plot_view == PlotView::Window,
                         "Window"
                     )
-                ).clicked() {
+                ).on_hover_text("3").clicked() {
                     self.plot_view = PlotView::Window;
                 }
 
+                if ui.add(
+                    egui::SelectableLabel::new(
+                        self.plot_view == PlotView::Complex,
+                        "Re/Im"
+                    )
+                ).on_hover_text("4").clicked() {
+                    self.plot_view = PlotView::Complex;
+                }
+
+                if ui.add_enabled(
+                    self.manual_bounds.contains(&self.plot_view),
+                    egui::Button::new("Reset View")
+                ).clicked() {
+                    self.manual_bounds.retain(|view| *view != self.plot_view);
+                }
+
                 ui.separator();
 
 
@@ -302,7 +1284,7 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> eframe::App for Conte
                         self.tracking == TimeSeriesTracking::Static,
                         "Static"
                     )
-                ).clicked() {
+                ).on_hover_text("T").clicked() {
                     self.tracking = TimeSeriesTracking::Static;
                 }
 
@@ -311,43 +1293,734 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> eframe::App for Conte
                         self.tracking == TimeSeriesTracking::Following,
                         "Following"
                     )
-                ).clicked() {
+                ).on_hover_text("T").clicked() {
                     self.tracking = TimeSeriesTracking::Following;
                 }
+
+                if ui.add(
+                    egui::SelectableLabel::new(
+                        self.tracking == TimeSeriesTracking::ZeroCrossing,
+                        "Zero Crossing"
+                    )
+                ).on_hover_text("T").clicked() {
+                    self.tracking = TimeSeriesTracking::ZeroCrossing;
+                }
+            });
+
+            ui.horizontal(|ui| {
+                match self.plot_view {
+                    PlotView::TimeSeries => {
+                        ui.label("Line:");
+                        ui.add(&mut self.time_series_stroke);
+                        if self.stereo {
+                            ui.label("Line B:");
+                            ui.add(&mut self.time_series_stroke_b);
+                        }
+
+                        ui.separator();
+
+                        ui.label("Span:");
+                        ui.add(
+                            egui::Slider::new(&mut self.scope_span, 1..=SCOPE_BLOCKS)
+                                .custom_formatter(|blocks, _| {
+                                    format!("{:.0} ms", 1000.0 * blocks * SIZE as f64 / SAMPLE_RATE as f64)
+                                })
+                                .custom_parser(|text| {
+                                    parse_ms_to_samples(text).map(|samples| (samples / SIZE as f64).round())
+                                })
+                        );
+
+                        ui.separator();
+
+                        ui.add_enabled_ui(self.tracking == TimeSeriesTracking::ZeroCrossing, |ui| {
+                            ui.label("Holdoff:");
+                            ui.add(
+                                egui::DragValue::new(&mut self.trigger_holdoff)
+                                    .range(0.0..=MAX_TRIGGER_HOLDOFF_MS)
+                                    .speed(1.0)
+                                    .suffix(" ms")
+                            ).on_hover_text("Ignore new triggers for this long after each trigger");
+                        });
+
+                        ui.separator();
+
+                        ui.checkbox(&mut self.freeze_on_clip, "Freeze on Clip");
+                        if self.scope_frozen {
+                            ui.colored_label(ui.visuals().error_fg_color, "Clipped");
+                            if ui.button("Resume").clicked() {
+                                self.scope_frozen = false;
+                            }
+                        }
+
+                        ui.separator();
+
+                        ui.add_enabled_ui(
+                            self.stereo && matches!(self.output_channel_b, AnalysisSource::Input(_)),
+                            |ui| {
+                                ui.label("Input Delay:");
+                                ui.add(
+                                    egui::DragValue::new(&mut self.overlay_delay)
+                                        .range(0..=MAX_ALIGNMENT_DELAY)
+                                        .custom_formatter(|samples, _| {
+                                            format!("{:.2} ms", 1000.0 * samples / SAMPLE_RATE as f64)
+                                        })
+                                        .custom_parser(parse_ms_to_samples)
+                                ).on_hover_text("Shift the input overlay to compensate for module latency");
+                                if ui.button("Auto").on_hover_text("Estimate the delay by cross-correlation").clicked() {
+                                    self.align_pending = true;
+                                }
+                            }
+                        );
+
+                        ui.separator();
+
+                        ui.checkbox(&mut self.show_envelope, "Envelope");
+
+                        ui.label("Attack:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.envelope_attack)
+                                .range(0.1..=1000.0)
+                                .suffix(" ms")
+                        );
+
+                        ui.label("Release:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.envelope_release)
+                                .range(0.1..=1000.0)
+                                .suffix(" ms")
+                        );
+                    },
+                    PlotView::Spectrum => {
+                        ui.label("Line:");
+                        ui.add(&mut self.spectrum_stroke);
+
+                        ui.separator();
+
+                        if ui.button("Single").clicked() {
+                            self.single_capture = true;
+                            self.single_capture_pending = true;
+                        }
+                        if ui.add_enabled(self.single_capture, egui::Button::new("Live")).clicked() {
+                            self.single_capture = false;
+                        }
+
+                        if ui.checkbox(&mut self.averaging, "Average").changed() {
+                            self.average_count = 0;
+                        }
+                        if ui.add_enabled(
+                            self.averaging,
+                            egui::DragValue::new(&mut self.average_frames)
+                                .range(2..=1000)
+                                .suffix(" frames")
+                        ).changed() {
+                            self.average_count = self.average_count.min(self.average_frames);
+                        }
+                        if self.averaging {
+                            ui.add(
+                                egui::ProgressBar::new(self.average_count as f32 / self.average_frames as f32)
+                                    .desired_width(80.0)
+                                    .text(format!("{}/{}", self.average_count, self.average_frames))
+                            );
+                            if ui.button("Restart").clicked() {
+                                self.average_count = 0;
+                            }
+                        }
+
+                        ui.checkbox(&mut self.smoothing_reset, "Fast Reset")
+                            .on_hover_text("Skip smoothing when the spectrum changes by more than this");
+                        ui.add_enabled(
+                            self.smoothing_reset,
+                            egui::DragValue::new(&mut self.smoothing_reset_threshold)
+                                .range(0.05..=4.0)
+                                .speed(0.01)
+                                .custom_formatter(|t, _| format!("{:.0}%", 100.0 * t))
+                                .custom_parser(parse_percent)
+                        );
+
+                        ui.separator();
+
+                        ui.label("Weighting:");
+                        egui::ComboBox::from_id_salt("WeightingSelect")
+                            .selected_text(self.weighting.to_string())
+                            .show_ui(ui, |ui| {
+                                for weighting in Weighting::iter() {
+                                    ui.selectable_value(&mut self.weighting, weighting, weighting.to_string());
+                                }
+                            });
+                        ui.checkbox(&mut self.weighted_readouts, "Weight Readouts")
+                            .on_hover_text("Apply the weighting to level readouts as well as the plot");
+
+                        ui.separator();
+
+                        ui.label("Scaling:");
+                        egui::ComboBox::from_id_salt("ScalingSelect")
+                            .selected_text(self.spectrum_scaling.to_string())
+                            .show_ui(ui, |ui| {
+                                for scaling in SpectrumScaling::iter() {
+                                    ui.selectable_value(&mut self.spectrum_scaling, scaling, scaling.to_string());
+                                }
+                            });
+
+                        ui.separator();
+
+                        ui.checkbox(&mut self.gate_enabled, "Gate");
+                        ui.add(
+                            egui::DragValue::new(&mut self.gate_threshold)
+                                .range(-140.0..=0.0)
+                                .suffix(" dB")
+                        );
+                        egui::ComboBox::from_id_salt("GateSelect")
+                            .selected_text(self.gate_mode.to_string())
+                            .show_ui(ui, |ui| {
+                                for mode in GateMode::iter() {
+                                    ui.selectable_value(&mut self.gate_mode, mode, mode.to_string());
+                                }
+                            });
+
+                        ui.separator();
+
+                        ui.checkbox(&mut self.show_harmonics, "Harmonics");
+                        ui.add(
+                            egui::DragValue::new(&mut self.harmonic_count)
+                                .range(1..=32)
+                        );
+
+                        ui.separator();
+
+                        if ui.checkbox(&mut self.show_max_hold, "Max Hold").changed() {
+                            self.reset_max_hold();
+                        }
+                        ui.add_enabled(
+                            !self.max_hold_infinite,
+                            egui::DragValue::new(&mut self.max_hold_time)
+                                .range(0.1..=60.0)
+                                .suffix(" s")
+                        );
+                        ui.checkbox(&mut self.max_hold_infinite, "∞");
+                        if ui.button("Reset Max").clicked() {
+                            self.reset_max_hold();
+                        }
+
+                        ui.separator();
+
+                        ui.label("Zoom:");
+                        if ui.add_enabled(
+                            self.output_buffer_freq_est > 0.0,
+                            egui::Button::new("Fundamental")
+                        ).clicked() {
+                            let center = (self.output_buffer_freq_est as f64).log2();
+                            self.manual_bounds.retain(|view| *view != PlotView::Spectrum);
+                            self.spectrum_zoom = Some((
+                                (center - self.zoom_octaves).max((1.0 / SIZE as f64).log2()),
+                                (center + self.zoom_octaves).min((0.5_f64).log2())
+                            ));
+                        }
+                        ui.add(
+                            egui::DragValue::new(&mut self.zoom_octaves)
+                                .range(0.1..=4.0)
+                                .speed(0.05)
+                                .prefix("±")
+                                .suffix(" oct")
+                        );
+                        if ui.add_enabled(self.spectrum_zoom.is_some(), egui::Button::new("Out")).clicked() {
+                            self.manual_bounds.retain(|view| *view != PlotView::Spectrum);
+                            self.spectrum_zoom = None;
+                        }
+
+                        ui.separator();
+
+                        ui.label("Markers:");
+                        if ui.button("Clear")
+                            .on_hover_text("Click the plot to place markers, drag to fit a slope")
+                            .clicked()
+                        {
+                            self.spectrum_markers = [None; 2];
+                            self.next_marker = 0;
+                            self.slope_range = None;
+                        }
+
+                        ui.separator();
+
+                        ui.label("Reference:");
+
+                        if ui.button("Capture").clicked() {
+                            self.spectrum_reference = Some(self.output_spectrum_magnitude);
+                        }
+
+                        if ui.button("Clear").clicked() {
+                            self.spectrum_reference = None;
+                            self.show_reference_difference = false;
+                        }
+
+                        ui.add_enabled(
+                            self.spectrum_reference.is_some(),
+                            egui::Checkbox::new(&mut self.show_reference_difference, "B−A (dB)")
+                        );
+
+                        ui.label("Noise:");
+                        egui::ComboBox::from_id_salt("NoiseReferenceSelect")
+                            .selected_text(self.noise_reference.to_string())
+                            .show_ui(ui, |ui| {
+                                for reference in NoiseReference::iter() {
+                                    ui.selectable_value(&mut self.noise_reference, reference, reference.to_string());
+                                }
+                            })
+                            .response
+                            .on_hover_text("Plot the deviation from an ideal noise spectrum");
+
+                        ui.label("Floor:");
+                        ui.add(
+                            egui::DragValue::new(&mut self.db_floor)
+                                .range(-200.0..=-20.0)
+                                .suffix(" dB")
+                        );
+                    },
+                    PlotView::Window => {
+                        ui.label("Window:");
+                        egui::ComboBox::from_id_salt("WindowSelect")
+                            .selected_text(self.window_function.to_string())
+                            .show_ui(ui, |ui| {
+                                for window_function in WindowFunction::options() {
+                                    if ui.add(
+                                        egui::SelectableLabel::new(
+                                            self.window_function == window_function,
+                                            window_function.to_string()
+                                        )
+                                    ).clicked() {
+                                        self.window_function = window_function;
+                                        self.fft_window_func = build_window_function(window_function);
+                                    }
+                                }
+                            });
+
+                        let parameter_changed = match &mut self.window_function {
+                            WindowFunction::Kaiser { beta } => ui.add(
+                                egui::Slider::new(beta, 0.0..=20.0)
+                                    .text("β")
+                            ).changed(),
+                            WindowFunction::Gaussian { sigma } => ui.add(
+                                egui::Slider::new(sigma, 0.05..=1.0)
+                                    .text("σ")
+                            ).changed(),
+                            _ => false
+                        };
+                        if parameter_changed {
+                            self.fft_window_func = build_window_function(self.window_function);
+                        }
+
+                        ui.separator();
+
+                        ui.label("File:");
+                        ui.text_edit_singleline(&mut self.window_path);
+                        ui.checkbox(&mut self.window_normalize, "Normalize");
+                        if ui.button("Load").clicked() {
+                            match load_window_function(&self.window_path, self.window_normalize) {
+                                Ok(window) => {
+                                    self.fft_window_func = window;
+                                    self.window_function = WindowFunction::Custom;
+                                    self.window_status = String::new();
+                                },
+                                Err(err) => self.window_status = err
+                            }
+                        }
+                        ui.label(&self.window_status);
+                    },
+                    PlotView::Complex => {}
+                }
+            });
+
+            if self.plot_view == PlotView::Spectrum {
+                ui.horizontal(|ui| {
+                    ui.label("Sweep Input:");
+                    ui.add_enabled_ui(!self.sweep.running(), |ui| {
+                        egui::ComboBox::from_id_salt("SweepChannelSelect")
+                            .selected_text(self.sweep.channel.to_string())
+                            .show_ui(ui, |ui| {
+                                for i in 0..IN {
+                                    ui.selectable_value(&mut self.sweep.channel, i, i.to_string());
+                                }
+                            });
+                        ui.add(
+                            egui::DragValue::new(&mut self.sweep.start_hz)
+                                .range(1.0..=(SAMPLE_RATE / 2) as f32)
+                                .suffix(" Hz")
+                        );
+                        ui.label("to");
+                        ui.add(
+                            egui::DragValue::new(&mut self.sweep.end_hz)
+                                .range(1.0..=(SAMPLE_RATE / 2) as f32)
+                                .suffix(" Hz")
+                        );
+                        ui.add(
+                            egui::DragValue::new(&mut self.sweep.points)
+                                .range(2..=MAX_SWEEP_POINTS)
+                                .suffix(" points")
+                        );
+                    });
+
+                    if self.sweep.running() {
+                        ui.add(
+                            egui::ProgressBar::new(self.sweep.progress() as f32 / self.sweep.points as f32)
+                                .desired_width(80.0)
+                                .text(format!("{}/{}", self.sweep.progress(), self.sweep.points))
+                        );
+                        if ui.button("Stop").clicked() {
+                            let frequency = self.sweep.stop();
+                            self.set_sweep_frequency(frequency);
+                        }
+                    } else {
+                        if ui.button("Run").clicked() {
+                            let frequency = self.sweep.start(self.input_widget.frequency(self.sweep.channel));
+                            self.set_sweep_frequency(frequency);
+                        }
+                        if ui.add_enabled(!self.sweep.response.is_empty(), egui::Button::new("Clear")).clicked() {
+                            self.sweep.response.clear();
+                        }
+                    }
+                });
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Estimator:");
+                egui::ComboBox::from_id_salt("EstimatorSelect")
+                    .selected_text(self.freq_estimator.to_string())
+                    .show_ui(ui, |ui| {
+                        for estimator in FrequencyEstimator::iter() {
+                            ui.selectable_value(&mut self.freq_estimator, estimator, estimator.to_string());
+                        }
+                    });
+
+                ui.checkbox(&mut self.median_filter, "Median")
+                    .on_hover_text("Median of the last K estimates, to reject outliers");
+                ui.add_enabled(
+                    self.median_filter,
+                    egui::DragValue::new(&mut self.median_length)
+                        .range(3..=FREQ_EST_HISTORY)
+                        .prefix("K = ")
+                );
+
+                ui.separator();
+
+                let (mean, std_dev) = mean_and_std_dev(&self.freq_est_history);
+                let hz = SAMPLE_RATE as f32;
+                ui.label(format!(
+                    "Frequency: {:.1} Hz ± {:.2}",
+                    hz * self.output_buffer_freq_est,
+                    hz * std_dev
+                ));
+                if mean > 0.0 && hz * std_dev < FREQ_LOCK_THRESHOLD_HZ {
+                    ui.colored_label(egui::Color32::GREEN, "locked");
+                } else {
+                    ui.weak("settling");
+                }
+
+                ui.separator();
+
+                ui.checkbox(&mut self.pause_on_menu, "Pause in Menus")
+                    .on_hover_text("Skip analysis while a dropdown is open");
+                ui.checkbox(&mut self.show_timing, "Timing");
+                if self.show_timing {
+                    for (name, ms) in TIMING_SECTIONS.iter().zip(self.timing) {
+                        ui.weak(format!("{}: {:.2} ms", name, ms));
+                    }
+                }
+
+                if let Some((centroid, bandwidth)) = self.spectral_centroid {
+                    ui.separator();
+                    ui.label(format!("Centroid: {:.0} Hz, Bandwidth: {:.0} Hz", centroid, bandwidth));
+                }
+
+                if let Some(correlation) = self.correlation {
+                    ui.separator();
+                    ui.label(format!("Correlation: {:+.2}", correlation));
+                    if self.correlation_history.iter().all(|&c| c < INVERSION_THRESHOLD) {
+                        ui.colored_label(ui.visuals().warn_fg_color, "⚠ inverted?")
+                            .on_hover_text("L and R have stayed anti-correlated; try inverting one output's polarity");
+                    }
+                }
+
+                if let Some(cancellation) = self.mono_cancellation {
+                    ui.separator();
+                    ui.label(format!("Mono vs L+R: {:.1} dB", cancellation))
+                        .on_hover_text("0 dB when the sides are in phase, lower when they cancel");
+                }
+
+                if self.plot_view == PlotView::Spectrum {
+                    for (name, marker) in ["A", "B"].iter().zip(self.spectrum_markers) {
+                        if let Some(x) = marker {
+                            let (hz, db) = self.marker_readout(x);
+                            ui.separator();
+                            ui.label(format!("{}: {:.1} Hz, {:.1} dBFS", name, hz, db));
+                        }
+                    }
+                    if let [Some(a), Some(b)] = self.spectrum_markers {
+                        let (_, db_a) = self.marker_readout(a);
+                        let (_, db_b) = self.marker_readout(b);
+                        ui.separator();
+                        ui.label(format!(
+                            "Δ: {:.0} ct ({:.2} oct), {:.1} dB",
+                            1200.0 * (b - a),
+                            b - a,
+                            db_b - db_a
+                        ));
+                    }
+                    if let Some((start, end)) = self.slope_range {
+                        // Fit absolute readout levels, so the slope doesn't depend on the plot's scaling
+                        let levels = (1..=(SIZE / 2))
+                            .map(|i| PlotPoint::new(self.output_spectrum_magnitude[i].x, self.readout_level(i)))
+                            .collect::<Vec<_>>();
+                        ui.separator();
+                        match fit_slope(
+                            &levels,
+                            start.min(end),
+                            start.max(end),
+                            self.db_floor
+                        ) {
+                            Some(slope) => ui.label(format!("Slope: {:.1} dB/oct", slope)),
+                            None => ui.weak("Slope: too few bins")
+                        };
+                    }
+                }
             });
             
             ui.separator();
             
-            match self.plot_view {
-                PlotView::TimeSeries => Plot::new("Time Series")
+            let plot = match self.plot_view {
+                PlotView::TimeSeries => {
+                    let name_a = self.source_name(self.output_channel);
+                    let name_b = self.source_name(self.output_channel_b);
+                    // Legend clicks select a trace instead of hiding it, so nothing is ever hidden
+                    let response = Plot::new("Time Series")
+                        .legend(Legend::default().hidden_items(std::iter::empty()))
+                        .show(ui, |plot_ui| {
+                            apply_default_bounds(
+                                plot_ui,
+                                &mut self.manual_bounds,
+                                self.plot_view,
+                                PlotBounds::from_min_max([0.0, -1.0], [(self.scope_span * SIZE) as f64, 1.0]),
+                                true
+                            );
+                            plot_ui.line(
+                                Line::new(name_a, &self.output_buffer_time_series[..(self.scope_span * SIZE)])
+                                    .id(TRACE_A_ID)
+                                    .stroke(self.time_series_stroke)
+                                    .highlight(self.stereo)
+                            );
+                            if self.stereo {
+                                plot_ui.line(
+                                    Line::new(name_b, &self.output_buffer_time_series_b[..(self.scope_span * SIZE)])
+                                        .id(TRACE_B_ID)
+                                        .stroke(self.time_series_stroke_b)
+                                );
+                            }
+                            if self.show_envelope {
+                                plot_ui.line(
+                                    Line::new("Envelope", &self.output_buffer_envelope[..(self.scope_span * SIZE)])
+                                );
+                            }
+                        });
+
+                    // Selecting trace B makes its source the primary analysis source
+                    if response.hovered_plot_item == Some(egui::Id::new(TRACE_B_ID))
+                        && ui.input(|i| i.pointer.primary_clicked())
+                    {
+                        std::mem::swap(&mut self.output_channel, &mut self.output_channel_b);
+                    }
+                    response
+                },
+                PlotView::Spectrum => Plot::new("Spectrum")
+                    .allow_drag(false)
                     .show(ui, |plot_ui| {
-                        plot_ui.set_plot_bounds(PlotBounds::from_min_max(
-                            [0.0, -1.0],
-                            [SIZE as f64, 1.0]
-                        ));
-                        plot_ui.set_auto_bounds(Vec2b::new(false, false));
-                        plot_ui.line(
-                            Line::new("Output", self.output_buffer_time_series.as_slice())
-                        );
+                        let (min_x, max_x) = self.spectrum_zoom
+                            .unwrap_or(((1.0 / SIZE as f64).log2(), (0.5_f64).log2()));
+                        match (&self.spectrum_reference, self.show_reference_difference) {
+                            _ if self.noise_reference != NoiseReference::None => {
+                                apply_default_bounds(
+                                    plot_ui,
+                                    &mut self.manual_bounds,
+                                    self.plot_view,
+                                    PlotBounds::from_min_max([min_x, -24.0], [max_x, 24.0]),
+                                    false
+                                );
+
+                                // Remove the mean offset so only the shape's departure remains
+                                let deviation = self.output_spectrum_magnitude[1..=(SIZE / 2)].iter().map(|point| {
+                                    let hz = point.x.exp2() * SAMPLE_RATE as f64;
+                                    PlotPoint::new(
+                                        point.x,
+                                        magnitude_to_db(point.y, self.db_floor)
+                                            - noise_reference_db(self.noise_reference, hz)
+                                    )
+                                }).collect::<Vec<_>>();
+                                let mean = deviation.iter().map(|point| point.y).sum::<f64>() / deviation.len() as f64;
+                                let points = deviation.into_iter()
+                                    .map(|point| PlotPoint::new(point.x, point.y - mean))
+                                    .collect::<Vec<_>>();
+                                plot_ui.line(
+                                    Line::new(format!("{} Deviation", self.noise_reference), PlotPoints::Owned(points))
+                                );
+                            },
+                            (Some(reference), true) => {
+                                apply_default_bounds(
+                                    plot_ui,
+                                    &mut self.manual_bounds,
+                                    self.plot_view,
+                                    PlotBounds::from_min_max([min_x, -24.0], [max_x, 24.0]),
+                                    false
+                                );
+
+                                let points = (1..=(SIZE / 2)).map(|i| {
+                                    let live = self.output_spectrum_magnitude[i];
+                                    [
+                                        live.x,
+                                        magnitude_to_db(live.y, self.db_floor)
+                                            - magnitude_to_db(reference[i].y, self.db_floor)
+                                    ]
+                                }).collect::<Vec<_>>();
+                                plot_ui.line(
+                                    Line::new("B−A", points)
+                                );
+                            },
+                            (reference, _) => {
+                                apply_default_bounds(
+                                    plot_ui,
+                                    &mut self.manual_bounds,
+                                    self.plot_view,
+                                    PlotBounds::from_min_max([min_x, 0.0], [max_x, self.spectrum_scaling.max_magnitude()]),
+                                    false
+                                );
+
+                                let bounds = *plot_ui.transform().bounds();
+                                let columns = plot_ui.transform().frame().width() as usize;
+                                if let Some(reference) = reference {
+                                    plot_ui.line(
+                                        Line::new("Reference", PlotPoints::Owned(decimate_to_columns(
+                                            &reference[1..=(SIZE / 2)],
+                                            bounds.min()[0],
+                                            bounds.max()[0],
+                                            columns
+                                        )))
+                                    );
+                                }
+                                plot_ui.line(
+                                    Line::new("Output", PlotPoints::Owned(decimate_to_columns(
+                                        &self.output_spectrum_magnitude[1..=(SIZE / 2)],
+                                        bounds.min()[0],
+                                        bounds.max()[0],
+                                        columns
+                                    )))
+                                        .stroke(self.spectrum_stroke)
+                                );
+                                if !self.sweep.response.is_empty() {
+                                    plot_ui.line(
+                                        Line::new("Response", &self.sweep.response[..])
+                                            .color(Color32::LIGHT_GREEN)
+                                    );
+                                }
+                                if self.show_max_hold {
+                                    plot_ui.line(
+                                        Line::new("Max Hold", PlotPoints::Owned(decimate_to_columns(
+                                            &self.output_spectrum_max[1..=(SIZE / 2)],
+                                            bounds.min()[0],
+                                            bounds.max()[0],
+                                            columns
+                                        )))
+                                    );
+                                }
+                                if self.stereo {
+                                    plot_ui.line(
+                                        Line::new("Output B", PlotPoints::Owned(decimate_to_columns(
+                                            &self.output_spectrum_magnitude_b[1..=(SIZE / 2)],
+                                            bounds.min()[0],
+                                            bounds.max()[0],
+                                            columns
+                                        )))
+                                    );
+                                }
+                            }
+                        }
+
+                        if plot_ui.response().clicked()
+                            && let Some(pointer) = plot_ui.pointer_coordinate()
+                        {
+                            self.spectrum_markers[self.next_marker] = Some(pointer.x);
+                            self.next_marker = (self.next_marker + 1) % 2;
+                        }
+                        if let Some(pointer) = plot_ui.pointer_coordinate() {
+                            if plot_ui.response().drag_started() {
+                                self.slope_range = Some((pointer.x, pointer.x));
+                            } else if plot_ui.response().dragged()
+                                && let Some((_, end)) = &mut self.slope_range
+                            {
+                                *end = pointer.x;
+                            }
+                        }
+                        if let Some((start, end)) = self.slope_range {
+                            for x in [start, end] {
+                                plot_ui.vline(
+                                    VLine::new("Slope", x)
+                                        .style(LineStyle::dotted_loose())
+                                );
+                            }
+                        }
+                        for (name, marker) in ["A", "B"].iter().zip(self.spectrum_markers) {
+                            if let Some(x) = marker {
+                                plot_ui.vline(
+                                    VLine::new(*name, x)
+                                        .color(Color32::YELLOW)
+                                );
+                            }
+                        }
+
+                        if self.show_harmonics && self.output_buffer_freq_est > 0.0 {
+                            for k in 1..=self.harmonic_count {
+                                let f = k as f64 * self.output_buffer_freq_est as f64;
+                                if f >= 0.5 {
+                                    break;
+                                }
+                                plot_ui.vline(
+                                    VLine::new(format!("H{}", k), f.log2())
+                                        .style(LineStyle::dashed_loose())
+                                );
+                            }
+                        }
                     }),
-                PlotView::Spectrum => Plot::new("Spectrum")
+                PlotView::Complex => Plot::new("Complex")
                     .show(ui, |plot_ui| {
-                        plot_ui.set_plot_bounds(PlotBounds::from_min_max(
-                            [(1.0 / SIZE as f64).log2(), 0.0],
-                            [(0.5_f64).log2(), 1.0]
-                        ));
-                        plot_ui.set_auto_bounds(Vec2b::new(false, false));
+                        apply_default_bounds(
+                            plot_ui,
+                            &mut self.manual_bounds,
+                            self.plot_view,
+                            PlotBounds::from_min_max([(1.0 / SIZE as f64).log2(), -1.0], [(0.5_f64).log2(), 1.0]),
+                            true
+                        );
+
+                        // Scaled so a full-scale sine peaks at 1, like the Absolute spectrum
+                        let scale = 2.0 / self.fft_window_func.iter().sum::<f32>() as f64;
+                        let (real, imaginary): (Vec<_>, Vec<_>) = (1..=(SIZE / 2)).map(|i| {
+                            let x = self.output_spectrum_magnitude[i].x;
+                            let value = self.output_spectrum_complex[i];
+                            (
+                                PlotPoint::new(x, scale * value.re as f64),
+                                PlotPoint::new(x, scale * value.im as f64)
+                            )
+                        }).unzip();
                         plot_ui.line(
-                            Line::new("Output", &self.output_spectrum_magnitude[0..(SIZE / 2)])
+                            Line::new("Real", PlotPoints::Owned(real))
+                        );
+                        plot_ui.line(
+                            Line::new("Imaginary", PlotPoints::Owned(imaginary))
                         );
                     }),
                 PlotView::Window => Plot::new("Window")
                     .show(ui, |plot_ui| {
-                        plot_ui.set_plot_bounds(PlotBounds::from_min_max(
-                            [0.0, 0.0],
-                            [SIZE as f64, 1.0]
-                        ));
-                        plot_ui.set_auto_bounds(Vec2b::new(false, false));
+                        apply_default_bounds(
+                            plot_ui,
+                            &mut self.manual_bounds,
+                            self.plot_view,
+                            PlotBounds::from_min_max([0.0, 0.0], [SIZE as f64, 1.0]),
+                            true
+                        );
 
                         let points = self.fft_window_func.iter().enumerate().map(|(x, &y)| {
                             [x as f64, y as f64]
@@ -356,9 +2029,14 @@ impl<const IN: usize, const OUT: usize, const SIZE: usize> eframe::App for Conte
                             Line::new("Output", points)
                         );
                     })
-            }
+            };
+            self.plot_rect = plot.response.rect;
         });
         
+        if let Some(index) = next_module {
+            self.switch_module(index);
+        }
+
         if self.running {
             ctx.request_repaint();
         }
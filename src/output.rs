@@ -1,5 +1,6 @@
 use std::sync::{Arc, Mutex};
-use cpal::{Device, HostId, SampleFormat, Stream, StreamConfig};
+use std::sync::atomic::{AtomicBool, Ordering};
+use cpal::{BufferSize, Device, Host, HostId, SampleFormat, Stream, SupportedBufferSize, SupportedStreamConfig};
 use cpal::traits::{HostTrait, DeviceTrait};
 use egui::Ui;
 use rtrb::{Consumer, Producer};
@@ -7,25 +8,223 @@ use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 use crate::Module;
+use crate::config::{load_settings, save_setting, Config};
+use crate::display::{parse_ms_to_samples, parse_number, DisplayPreferences};
 use crate::input;
+use crate::rng::Rng;
 
 pub const EVENT_UPDATE_INTERVAL: usize = 1024;
 pub const SAMPLE_RATE: usize = 48_000;
+pub const MAX_DELAY: usize = SAMPLE_RATE / 10;
+pub const SCOPE_BLOCKS: usize = 4;
+// One spare block so a full-span trace can still step back to a trigger or phase
+pub const SCOPE_RING_BLOCKS: usize = SCOPE_BLOCKS + 1;
+pub const MAX_TAPS: usize = 4;
+pub const FADE_LENGTH: usize = SAMPLE_RATE / 200;
+pub const BUFFER_SIZE_OPTIONS: [u32; 6] = [64, 128, 256, 512, 1024, 2048];
+
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AnalysisSource {
+    Channel(usize),
+    Mono,
+    Tap(usize),
+    Input(usize)
+}
+
+impl std::fmt::Display for AnalysisSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalysisSource::Channel(channel) => write!(f, "{}", channel),
+            AnalysisSource::Mono => write!(f, "Mono"),
+            AnalysisSource::Tap(tap) => write!(f, "Tap {}", tap),
+            AnalysisSource::Input(input) => write!(f, "In {}", input)
+        }
+    }
+}
 
 
-pub struct OutputBuffer<const OUT: usize, const SIZE: usize> {
+pub struct OutputBuffer<const IN: usize, const OUT: usize, const SIZE: usize> {
     pub buffer: [[f32; SIZE]; OUT],
     pub index: usize,
-    pub counter: usize
+    pub counter: usize,
+    pub scope: [Vec<f32>; OUT],
+    pub scope_index: usize,
+    pub master: [[f32; SIZE]; 2],
+    pub mono: [f32; SIZE],
+    pub mono_scope: Vec<f32>,
+    pub taps: [[f32; SIZE]; MAX_TAPS],
+    pub tap_scope: [Vec<f32>; MAX_TAPS],
+    pub inputs: [[f32; SIZE]; IN],
+    pub input_scope: [Vec<f32>; IN],
+    pub clipped: bool,
+    pub history_dropped: usize,
+    pub faded_out: bool
 }
 
-impl<const OUT: usize, const SIZE: usize> OutputBuffer<OUT, SIZE> {
+impl<const IN: usize, const OUT: usize, const SIZE: usize> OutputBuffer<IN, OUT, SIZE> {
     pub fn new() -> Self {
         OutputBuffer {
             buffer: [[0.0; SIZE]; OUT],
             index: 0,
-            counter: 0
+            counter: 0,
+            scope: [(); OUT].map(|_| vec![0.0; SIZE * SCOPE_RING_BLOCKS]),
+            scope_index: 0,
+            master: [[0.0; SIZE]; 2],
+            mono: [0.0; SIZE],
+            mono_scope: vec![0.0; SIZE * SCOPE_RING_BLOCKS],
+            taps: [[0.0; SIZE]; MAX_TAPS],
+            tap_scope: [(); MAX_TAPS].map(|_| vec![0.0; SIZE * SCOPE_RING_BLOCKS]),
+            inputs: [[0.0; SIZE]; IN],
+            input_scope: [(); IN].map(|_| vec![0.0; SIZE * SCOPE_RING_BLOCKS]),
+            clipped: false,
+            history_dropped: 0,
+            faded_out: true
+        }
+    }
+
+    pub fn source_buffer(&self, source: AnalysisSource) -> &[f32] {
+        match source {
+            AnalysisSource::Channel(channel) => &self.buffer[channel],
+            AnalysisSource::Mono => &self.mono,
+            AnalysisSource::Tap(tap) => &self.taps[tap],
+            AnalysisSource::Input(input) => &self.inputs[input]
+        }
+    }
+
+    pub fn source_scope(&self, source: AnalysisSource) -> &[f32] {
+        match source {
+            AnalysisSource::Channel(channel) => &self.scope[channel],
+            AnalysisSource::Mono => &self.mono_scope,
+            AnalysisSource::Tap(tap) => &self.tap_scope[tap],
+            AnalysisSource::Input(input) => &self.input_scope[input]
+        }
+    }
+}
+
+impl<const IN: usize, const OUT: usize, const SIZE: usize> Default for OutputBuffer<IN, OUT, SIZE> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+pub struct DelayLine {
+    buffer: [f32; MAX_DELAY + 1],
+    index: usize
+}
+
+impl DelayLine {
+    pub fn new() -> Self {
+        DelayLine {
+            buffer: [0.0; MAX_DELAY + 1],
+            index: 0
+        }
+    }
+
+    pub fn process(&mut self, sample: f32, delay: usize) -> f32 {
+        let len = self.buffer.len();
+        self.buffer[self.index] = sample;
+        let out = self.buffer[(self.index + len - delay.min(MAX_DELAY)) % len];
+        self.index = (self.index + 1) % len;
+        out
+    }
+}
+
+impl Default for DelayLine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[derive(Clone, Copy)]
+pub struct TestTone {
+    pub enabled: bool,
+    pub frequency_hz: f32,
+    pub level_dbfs: f32
+}
+
+impl TestTone {
+    pub fn new() -> Self {
+        TestTone {
+            enabled: false,
+            frequency_hz: 1000.0,
+            level_dbfs: -20.0
+        }
+    }
+}
+
+impl Default for TestTone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+#[derive(Clone, Copy)]
+pub struct DitherSettings {
+    pub enabled: bool,
+    pub bits: u32,
+    pub noise_shaping: bool
+}
+
+impl DitherSettings {
+    pub fn new() -> Self {
+        DitherSettings {
+            enabled: false,
+            bits: 16,
+            noise_shaping: false
+        }
+    }
+}
+
+impl Default for DitherSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// TPDF dither and requantization to `bits`, with optional first-order error feedback
+pub struct Dither {
+    settings: DitherSettings,
+    rng: Rng,
+    error: [f32; 2]
+}
+
+impl Dither {
+    pub fn new() -> Self {
+        Dither {
+            settings: DitherSettings::new(),
+            rng: Rng::new(0x9E37_79B9_7F4A_7C15),
+            error: [0.0; 2]
+        }
+    }
+
+    pub fn set(&mut self, settings: DitherSettings) {
+        self.settings = settings;
+        self.error = [0.0; 2];
+    }
+
+    pub fn process(&mut self, sample: f32, side: usize) -> f32 {
+        if !self.settings.enabled {
+            return sample;
         }
+        let lsb = 2.0_f32.powi(1 - self.settings.bits as i32);
+        let shaped = match self.settings.noise_shaping {
+            true => sample - self.error[side],
+            false => sample
+        };
+        let tpdf = (self.rng.next_f32() - self.rng.next_f32()) * lsb;
+        let out = ((shaped + tpdf) / lsb).round() * lsb;
+        self.error[side] = out - shaped;
+        out
+    }
+}
+
+impl Default for Dither {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -51,6 +250,9 @@ impl std::fmt::Display for OutputMap {
 pub enum Command {
     SetMap(OutputMap),
     SetVolume(f32),
+    SetTrim(f32),
+    SetDelay(usize),
+    SetPolarity(bool),
     SetEnabled,
     SetDisabled
 }
@@ -60,6 +262,9 @@ pub enum Command {
 pub struct Channel {
     output_map: OutputMap,
     volume: f32,
+    trim_db: f32,
+    delay: usize,
+    inverted: bool,
     enabled: bool
 }
 
@@ -68,16 +273,33 @@ impl Channel {
         Channel {
             output_map: OutputMap::default(),
             volume: 0.5,
+            trim_db: 0.0,
+            delay: 0,
+            inverted: false,
             enabled: true,
         }
     }
 
+    pub fn gain(&self) -> f32 {
+        let gain = self.volume * 10.0_f32.powf(self.trim_db / 20.0);
+        match self.inverted {
+            true => -gain,
+            false => gain
+        }
+    }
+
     pub fn handle_command(&mut self, command: Command) {
         match command {
             Command::SetMap(output_map) =>
                 self.output_map = output_map,
             Command::SetVolume(volume) =>
                 self.volume = volume,
+            Command::SetTrim(trim_db) =>
+                self.trim_db = trim_db.clamp(-12.0, 12.0),
+            Command::SetDelay(delay) =>
+                self.delay = delay.min(MAX_DELAY),
+            Command::SetPolarity(inverted) =>
+                self.inverted = inverted,
             Command::SetEnabled =>
                 self.enabled = true,
             Command::SetDisabled =>
@@ -86,6 +308,12 @@ impl Channel {
     }
 }
 
+impl Default for Channel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 
 pub enum ControlMessage {
     OutputControl {
@@ -95,32 +323,71 @@ pub enum ControlMessage {
     InputControl {
         channel: usize,
         command: input::Command
-    }
+    },
+    InputMix {
+        input: usize,
+        channel: usize,
+        gain: f32
+    },
+    SetDither(DitherSettings),
+    SetTestTone(TestTone)
+}
+
+
+// Falls back to the default host when the preferred one is missing or has no output device
+pub fn select_host(preferred: Option<&str>) -> Host {
+    preferred
+        .and_then(|name| cpal::available_hosts().into_iter().find(|id| id.name() == name))
+        .and_then(|id| cpal::host_from_id(id).ok())
+        .filter(|host| host.default_output_device().is_some())
+        .unwrap_or_else(cpal::default_host)
 }
 
+// Falls back to the host's default output when the preferred device has gone away
+pub fn select_device(host: &Host, preferred: Option<&str>) -> Device {
+    preferred
+        .and_then(|name| {
+            host.output_devices().ok()?
+                .find(|device| device.name().is_ok_and(|device_name| device_name == name))
+        })
+        .unwrap_or_else(|| host.default_output_device().unwrap())
+}
 
 pub fn build_output_stream<M, const IN: usize, const OUT: usize, const SIZE: usize>(
+    host: &Host,
     mut module: M,
     mut receiver: Consumer<ControlMessage>,
     mut sender: Producer<input::Event<IN>>,
-    output_buffer: Arc<Mutex<OutputBuffer<OUT, SIZE>>>
+    mut history_sender: Producer<[f32; 2]>,
+    output_buffer: Arc<Mutex<OutputBuffer<IN, OUT, SIZE>>>,
+    playing: Arc<AtomicBool>
 ) -> Stream
 where
     M: 'static + Module<IN, OUT> + Send 
 {
-    let host = cpal::default_host();
-    let device = host.default_output_device().unwrap();
+    let device = select_device(host, load_settings().get("device"));
     let config = device.default_output_config().unwrap();
 
     let channels = config.channels() as usize;
-    assert!(channels >= 2);
     assert!(config.sample_format() == SampleFormat::F32);
 
+    let mut stream_config = config.config();
+    if let Some(frames) = applied_buffer_size(&config) {
+        stream_config.buffer_size = BufferSize::Fixed(frames);
+    }
+
     let mut input_channels = [(); IN].map(|_| input::Channel::new());
+    let mut input_mixer = input::identity_mixer::<IN>();
     let mut output_channels = [(); OUT].map(|_| Channel::new());
+    let mut delay_lines = [(); OUT].map(|_| DelayLine::new());
+    let mut dither = Dither::new();
+    let mut fade = 0.0_f32;
+    let mut since_event = 0;
+    let mut test_tone = TestTone::new();
+    let mut test_tone_phase = 0.0_f32;
 
     device.build_output_stream(
-        &config.config(),
+        &stream_config,
 
         // Audio Callback
         move |data: &mut [f32], _| {
@@ -134,56 +401,135 @@ where
                     ControlMessage::OutputControl { channel, command  } => {
                         output_channels[channel].handle_command(command);
                     },
+                    ControlMessage::InputMix { input, channel, gain } => {
+                        input_mixer[input][channel] = gain;
+                    },
+                    ControlMessage::SetDither(settings) => {
+                        dither.set(settings);
+                    },
+                    ControlMessage::SetTestTone(tone) => {
+                        test_tone = tone;
+                    },
                 }
             }
 
+            // One lock per callback; a trailing partial frame is left silent
             let mut output_buffer = output_buffer.lock().unwrap();
-            for out_frame in data.chunks_mut(channels) {
+            let frames = data.len() / channels;
+            data[frames * channels..].fill(0.0);
+            for out_frame in data.chunks_exact_mut(channels) {
 
                 // Handle module inputs
+                let mut generated = [0.0; IN];
+                for i in 0..IN {
+                    generated[i] = input_channels[i].process();
+                }
                 let mut inputs = [0.0; IN];
                 for i in 0..IN {
-                    inputs[i] = input_channels[i].process();
+                    for j in 0..IN {
+                        inputs[i] += input_mixer[i][j] * generated[j];
+                    }
                 }
                 module.map_inputs(&inputs);
                 
                 // Handle module outputs
                 let mut outputs = [0.0; OUT];
                 module.map_outputs(&mut outputs);
+                for i in 0..OUT {
+                    outputs[i] = delay_lines[i].process(outputs[i], output_channels[i].delay);
+                }
 
-                out_frame[0] = 0.0;
-                out_frame[1] = 0.0;
+                let mut master = [0.0_f32; 2];
                 for i in 0..OUT {
                     if !output_channels[i].enabled {
                         continue;
                     }
 
-                    let scale = output_channels[i].volume;
+                    let scale = output_channels[i].gain();
                     match output_channels[i].output_map {
                         OutputMap::Both => {
-                            out_frame[0] += scale * outputs[i];
-                            out_frame[1] += scale * outputs[i];
+                            master[0] += scale * outputs[i];
+                            master[1] += scale * outputs[i];
                         },
                         OutputMap::Left => {
-                            out_frame[0] += scale * outputs[i];
+                            master[0] += scale * outputs[i];
                         },
                         OutputMap::Right => {
-                            out_frame[1] += scale * outputs[i];
+                            master[1] += scale * outputs[i];
                         }
                     };
                 }
 
+                // Reference tone on the master bus, bypassing the module. There is no limiter for
+                // it to respect: a tone that pushes the bus over full scale shows up as clipping.
+                if test_tone.enabled {
+                    test_tone_phase = (test_tone_phase + test_tone.frequency_hz / SAMPLE_RATE as f32).fract();
+                    let tone = 10.0_f32.powf(test_tone.level_dbfs / 20.0) * (2.0 * std::f32::consts::PI * test_tone_phase).sin();
+                    master[0] += tone;
+                    master[1] += tone;
+                }
+
+                // Ramp toward the transport state so starting and stopping don't click
+                let target = if playing.load(Ordering::Relaxed) { 1.0 } else { 0.0 };
+                fade = if fade < target {
+                    (fade + 1.0 / FADE_LENGTH as f32).min(target)
+                } else {
+                    (fade - 1.0 / FADE_LENGTH as f32).max(target)
+                };
+                master[0] *= fade;
+                master[1] *= fade;
+
+                // Dither is the last stage before the device. The master bus has no limiter to
+                // follow (overs are left in so the clip indicator can report them), so running
+                // this after a limiter is out of scope.
+                master[0] = dither.process(master[0], 0);
+                master[1] = dither.process(master[1], 1);
+
+                // Extra device channels stay silent, and devices with fewer than two only get the left side
+                out_frame.fill(0.0);
+                for (out, value) in out_frame.iter_mut().zip(master) {
+                    *out = value;
+                }
+
+                // Keep recent output for retroactive capture; a full ring means the UI stalled
+                if history_sender.push([master[0], master[1]]).is_err() {
+                    output_buffer.history_dropped += 1;
+                }
+
                 // Copy to output buffer
-                for i in 0..OUT {
-                    let index = output_buffer.index;
-                    output_buffer.buffer[i][index] = outputs[i];
+                let index = output_buffer.index;
+                let scope_index = output_buffer.scope_index;
+                for (i, &output) in outputs.iter().enumerate() {
+                    output_buffer.buffer[i][index] = output;
+                    output_buffer.scope[i][scope_index] = output;
+                }
+                // Latched until the UI thread takes it
+                if master[0].abs() > 1.0 || master[1].abs() > 1.0 {
+                    output_buffer.clipped = true;
+                }
+                output_buffer.master[0][index] = master[0];
+                output_buffer.master[1][index] = master[1];
+                output_buffer.mono[index] = master[0] + master[1];
+                output_buffer.mono_scope[scope_index] = master[0] + master[1];
+                for (i, &input) in inputs.iter().enumerate() {
+                    output_buffer.inputs[i][index] = input;
+                    output_buffer.input_scope[i][scope_index] = input;
+                }
+                for (i, &tap) in module.debug_taps().iter().take(MAX_TAPS).enumerate() {
+                    output_buffer.taps[i][index] = tap;
+                    output_buffer.tap_scope[i][scope_index] = tap;
                 }
                 output_buffer.index = (output_buffer.index + 1) % SIZE;
+                output_buffer.scope_index = (output_buffer.scope_index + 1) % (SIZE * SCOPE_RING_BLOCKS);
                 output_buffer.counter += 1;
             }
+            output_buffer.faded_out = fade == 0.0;
 
-            // Send state of inputs to main thread.  Ignore Errors.
-            if output_buffer.index % EVENT_UPDATE_INTERVAL == 0 {
+            // Send state of inputs to main thread.  Ignore Errors.  Counting frames rather than
+            // checking the ring index keeps the rate steady whatever size the callback is handed.
+            since_event += frames;
+            if since_event >= EVENT_UPDATE_INTERVAL {
+                since_event %= EVENT_UPDATE_INTERVAL;
                 sender.push(input::Event::State(input_channels)).ok();
             }
         },
@@ -199,20 +545,27 @@ pub struct Widget<const N: usize> {
     hosts: Vec<(HostId, String)>,
     selected_host_id: HostId,
     selected_host_name: String,
+    active_host_name: String,
+    host_status: String,
     devices: Vec<(Device, String)>,
     selected_device: Device,
     selected_device_index: usize,
     selected_device_name: String,
-    config: StreamConfig,
-    models: [Channel; N]
+    active_device_name: String,
+    active_device_channels: usize,
+    buffer_size: Option<u32>,
+    active_buffer_size: Option<u32>,
+    models: [Channel; N],
+    dither: DitherSettings,
+    test_tone: TestTone
 }
 
 impl<const N: usize> Widget<N> {
-    pub fn new() -> Self {
+    pub fn new(active_host_id: HostId) -> Self {
         let hosts = cpal::available_hosts().into_iter()
             .map(|host| (host, host.name().to_owned()))
             .collect();
-        let selected_host = cpal::default_host();
+        let selected_host = cpal::host_from_id(active_host_id).unwrap();
         let selected_host_id = selected_host.id();
         let selected_host_name = selected_host_id.name().to_string();
         
@@ -222,30 +575,85 @@ impl<const N: usize> Widget<N> {
                 (dev, name)
             })
             .collect();
-        let selected_device = selected_host.default_output_device().unwrap();
+        let selected_device = select_device(&selected_host, load_settings().get("device"));
         let selected_device_name = selected_device.name().unwrap();
         let selected_device_index = devices.iter()
             .enumerate()
             .find(|(_, (_, dev_name))| *dev_name == selected_device_name)
             .unwrap()
             .0;
-        
-        let config = selected_device.default_output_config().unwrap().config();
+        let active_config = selected_device.default_output_config().ok();
+        let active_device_channels = active_config.as_ref()
+            .map(|config| config.channels() as usize)
+            .unwrap_or(0);
+        let active_buffer_size = active_config.as_ref().and_then(applied_buffer_size);
         
         Widget {
             hosts,
             selected_host_id,
-            selected_host_name,
+            selected_host_name: selected_host_name.clone(),
+            active_host_name: selected_host_name,
+            host_status: String::new(),
             devices,
             selected_device,
             selected_device_index,
-            selected_device_name,
-            config,
-            models: [Channel::new(); N]
+            selected_device_name: selected_device_name.clone(),
+            active_device_name: selected_device_name,
+            active_device_channels,
+            buffer_size: active_buffer_size,
+            active_buffer_size,
+            models: [Channel::new(); N],
+            dither: DitherSettings::new(),
+            test_tone: TestTone::new()
+        }
+    }
+
+    // Module outputs are mixed onto the L/R bus, which is then written to the first two device
+    // channels; describe whatever that leaves unheard or unused
+    fn routing_warning(&self) -> Option<String> {
+        let channels = self.active_device_channels;
+        if channels < 2 {
+            let dropped = (0..N)
+                .filter(|&i| self.models[i].enabled && self.models[i].output_map == OutputMap::Right)
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>();
+            let detail = match dropped.is_empty() {
+                true => String::new(),
+                false => format!("; outputs {} are not heard", dropped.join(", "))
+            };
+            Some(format!("{} has {} channel(s): the right bus is dropped{}", self.active_device_name, channels, detail))
+        } else if N > channels {
+            Some(format!("{} module outputs share L/R on a {}-channel device", N, channels))
+        } else if channels > 2 {
+            Some(format!("device channels 2–{} are silent", channels - 1))
+        } else {
+            None
+        }
+    }
+
+    pub fn settings(&self) -> Config {
+        let mut config = Config::new();
+        config.set("host", &self.active_host_name);
+        config.set("device", &self.active_device_name);
+        config.set("buffer_size", buffer_size_name(self.active_buffer_size));
+        for (i, model) in self.models.iter().enumerate() {
+            config.set(&format!("output{}.enabled", i), model.enabled);
+            config.set(&format!("output{}.map", i), model.output_map);
+            config.set(&format!("output{}.volume", i), model.volume);
+            config.set(&format!("output{}.trim_db", i), model.trim_db);
+            config.set(&format!("output{}.delay_samples", i), model.delay);
+            config.set(&format!("output{}.inverted", i), model.inverted);
         }
+        config.set("test_tone.enabled", self.test_tone.enabled);
+        config.set("test_tone.frequency_hz", self.test_tone.frequency_hz);
+        config.set("test_tone.level_dbfs", self.test_tone.level_dbfs);
+        config.set("dither.enabled", self.dither.enabled);
+        config.set("dither.bits", self.dither.bits);
+        config.set("dither.noise_shaping", self.dither.noise_shaping);
+        config
     }
 
-    pub fn render(&mut self, ui: &mut Ui, sender: &mut Producer<ControlMessage>) -> Option<Stream> {
+    pub fn render(&mut self, ui: &mut Ui, sender: &mut Producer<ControlMessage>, display: &DisplayPreferences) -> Option<Stream> {
         ui.heading("Outputs");
         ui.separator();
         
@@ -296,16 +704,63 @@ impl<const N: usize> Widget<N> {
 
                     ui.label("Volume:");
                     ui.horizontal(|ui| {
-                    if ui.add(
-                        egui::Slider::new(&mut self.models[index].volume, 0.0..=1.0)
-                            .custom_formatter(|f, _| format!("{:.2}%", 100.0 * f))
-                    ).changed() {
-                        sender.push(ControlMessage::OutputControl {
-                            channel: index,
-                            command: Command::SetVolume(self.models[index].volume)
-                        }).unwrap();
-                    };
-                });
+                        if ui.add(
+                            egui::Slider::new(&mut self.models[index].volume, 0.0..=1.0)
+                                .custom_formatter(|f, _| display.format_volume(f))
+                                .custom_parser(|text| display.parse_volume(text))
+                        ).changed() {
+                            sender.push(ControlMessage::OutputControl {
+                                channel: index,
+                                command: Command::SetVolume(self.models[index].volume)
+                            }).unwrap();
+                        };
+                    });
+
+                    ui.end_row();
+
+                    ui.label("Trim:");
+                    ui.horizontal(|ui| {
+                        if ui.add(
+                            egui::Slider::new(&mut self.models[index].trim_db, -12.0..=12.0)
+                                .custom_formatter(|db, _| format!("{:+.*} dB", display.decimals, db))
+                                .custom_parser(|text| parse_number(text.trim().trim_end_matches("dB")))
+                        ).changed() {
+                            sender.push(ControlMessage::OutputControl {
+                                channel: index,
+                                command: Command::SetTrim(self.models[index].trim_db)
+                            }).unwrap();
+                        };
+                    });
+
+                    ui.end_row();
+
+                    ui.label("Delay:");
+                    ui.horizontal(|ui| {
+                        if ui.add(
+                            egui::Slider::new(&mut self.models[index].delay, 0..=MAX_DELAY)
+                                .custom_formatter(|d, _| format!("{} ms", display.format_number(1000.0 * d / SAMPLE_RATE as f64)))
+                                .custom_parser(parse_ms_to_samples)
+                        ).changed() {
+                            sender.push(ControlMessage::OutputControl {
+                                channel: index,
+                                command: Command::SetDelay(self.models[index].delay)
+                            }).unwrap();
+                        };
+                    });
+
+                    ui.end_row();
+
+                    ui.label("Invert:");
+                    ui.horizontal(|ui| {
+                        if ui.add(
+                            egui::Checkbox::new(&mut self.models[index].inverted, "")
+                        ).changed() {
+                            sender.push(ControlMessage::OutputControl {
+                                channel: index,
+                                command: Command::SetPolarity(self.models[index].inverted)
+                            }).unwrap();
+                        };
+                    });
                 });
             
             ui.separator();
@@ -317,23 +772,32 @@ impl<const N: usize> Widget<N> {
             .show(ui, |ui| {
                 ui.label("Host:");
                 egui::ComboBox::from_id_salt("HostSelect")
-                    .selected_text(format!("{}", self.selected_host_name))
+                    .selected_text(self.selected_host_name.clone())
                     .show_ui(ui, |ui| {
                         for (host, host_name) in &self.hosts {
                             if ui
                                 .selectable_value(&mut self.selected_host_id, *host, host_name)
                                 .clicked() {
-                                    self.selected_host_id = host.clone();
+                                    self.selected_host_id = *host;
                                     self.selected_host_name = self.selected_host_id.name().to_string();
+                                    self.host_status = match save_setting("host", &self.selected_host_name) {
+                                        Ok(()) => "Preferred on next start".to_string(),
+                                        Err(err) => format!("Error: {}", err)
+                                    };
                             }
                         }
                     });
 
                 ui.end_row();
 
+                ui.label("Active:");
+                ui.label(format!("{} {}", self.active_host_name, self.host_status));
+
+                ui.end_row();
+
                 ui.label("Device:");
                 egui::ComboBox::from_id_salt("DeviceSelect")
-                    .selected_text(format!("{}", self.selected_device_name))
+                    .selected_text(self.selected_device_name.clone())
                     .show_ui(ui, |ui| {
                         for (i, (_, device_name)) in self.devices.iter().enumerate() {
                             if ui
@@ -342,15 +806,100 @@ impl<const N: usize> Widget<N> {
                                     let device = &self.devices.get(self.selected_device_index).unwrap().0;
                                     self.selected_device = device.clone();
                                     self.selected_device_name = self.selected_device.name().unwrap();
-                                    println!("device changed: {}", self.selected_device_name);
+                                    self.host_status = match save_setting("device", &self.selected_device_name) {
+                                        Ok(()) => "Preferred on next start".to_string(),
+                                        Err(err) => format!("Error: {}", err)
+                                    };
                             };
                         }
                     });
 
                 ui.end_row();
+
+                ui.label("Buffer:");
+                egui::ComboBox::from_id_salt("BufferSizeSelect")
+                    .selected_text(buffer_size_name(self.buffer_size))
+                    .show_ui(ui, |ui| {
+                        let options = std::iter::once(None).chain(BUFFER_SIZE_OPTIONS.map(Some));
+                        for buffer_size in options {
+                            if ui
+                                .selectable_value(&mut self.buffer_size, buffer_size, buffer_size_name(buffer_size))
+                                .clicked() {
+                                    let saved = match buffer_size {
+                                        Some(frames) => save_setting("buffer_size", frames),
+                                        None => save_setting("buffer_size", "Default")
+                                    };
+                                    self.host_status = match saved {
+                                        Ok(()) => "Preferred on next start".to_string(),
+                                        Err(err) => format!("Error: {}", err)
+                                    };
+                            }
+                        }
+                    });
+
+                ui.end_row();
+
+                if let Some(warning) = self.routing_warning() {
+                    ui.label("Routing:");
+                    ui.colored_label(ui.visuals().warn_fg_color, warning);
+
+                    ui.end_row();
+                }
+
+                ui.label("Dither:");
+                ui.horizontal(|ui| {
+                    let mut changed = ui.checkbox(&mut self.dither.enabled, "").changed();
+                    ui.add_enabled_ui(self.dither.enabled, |ui| {
+                        changed |= ui.add(
+                            egui::Slider::new(&mut self.dither.bits, 4..=24)
+                                .suffix(" bit")
+                        ).changed();
+                        changed |= ui.checkbox(&mut self.dither.noise_shaping, "Shape").changed();
+                    });
+                    if changed {
+                        sender.push(ControlMessage::SetDither(self.dither)).unwrap();
+                    }
+                });
+
+                ui.end_row();
+
+                ui.label("Test Tone:");
+                ui.horizontal(|ui| {
+                    let mut changed = ui.checkbox(&mut self.test_tone.enabled, "").changed();
+                    changed |= ui.add(
+                        egui::DragValue::new(&mut self.test_tone.frequency_hz)
+                            .range(1.0..=(SAMPLE_RATE / 2) as f32)
+                            .suffix(" Hz")
+                    ).changed();
+                    changed |= ui.add(
+                        egui::DragValue::new(&mut self.test_tone.level_dbfs)
+                            .range(-120.0..=0.0)
+                            .suffix(" dBFS")
+                    ).changed();
+                    if changed {
+                        sender.push(ControlMessage::SetTestTone(self.test_tone)).unwrap();
+                    }
+                });
+
+                ui.end_row();
             });
 
         None
     }
 }
 
+// The saved buffer size, if the device supports it; otherwise the stream runs at its default
+fn applied_buffer_size(config: &SupportedStreamConfig) -> Option<u32> {
+    let frames = load_settings().parse::<u32>("buffer_size")?;
+    match config.buffer_size() {
+        SupportedBufferSize::Range { min, max } if (*min..=*max).contains(&frames) => Some(frames),
+        _ => None
+    }
+}
+
+fn buffer_size_name(buffer_size: Option<u32>) -> String {
+    match buffer_size {
+        Some(frames) => format!("{} frames", frames),
+        None => "Default".to_string()
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+use std::io::BufWriter;
+
+use egui::Ui;
+use rtrb::Consumer;
+
+use crate::config::{capture_dir, create_unique};
+use crate::display::parse_seconds;
+use crate::output::SAMPLE_RATE;
+
+pub const MAX_CAPTURE_SECONDS: usize = 10;
+
+
+pub struct Widget {
+    receiver: Consumer<[f32; 2]>,
+    history: VecDeque<[f32; 2]>,
+    capture_seconds: f32,
+    received: usize,
+    drops: VecDeque<(usize, usize)>,
+    status: String
+}
+
+impl Widget {
+    pub fn new(receiver: Consumer<[f32; 2]>) -> Self {
+        Widget {
+            receiver,
+            history: VecDeque::with_capacity(MAX_CAPTURE_SECONDS * SAMPLE_RATE),
+            capture_seconds: 5.0,
+            received: 0,
+            drops: VecDeque::new(),
+            status: String::new()
+        }
+    }
+
+    pub fn update(&mut self) {
+        while let Ok(frame) = self.receiver.pop() {
+            if self.history.len() == MAX_CAPTURE_SECONDS * SAMPLE_RATE {
+                self.history.pop_front();
+            }
+            self.history.push_back(frame);
+            self.received += 1;
+        }
+
+        // Gaps that have scrolled out of the history can't affect a capture any more
+        while self.drops.front().is_some_and(|&(position, _)| position + self.history.len() < self.received) {
+            self.drops.pop_front();
+        }
+    }
+
+    // Frames the audio thread couldn't queue; the gap is recorded at the current stream position,
+    // so only captures spanning it report it
+    pub fn add_dropped(&mut self, frames: usize) {
+        if frames > 0 {
+            self.drops.push_back((self.received, frames));
+        }
+    }
+
+    // Returns the path written and the frames dropped within the captured window
+    pub fn dump(&self) -> Result<(String, usize), hound::Error> {
+        let (path, file) = create_unique(&capture_dir()?, "capture", "wav")?;
+
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: SAMPLE_RATE as u32,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float
+        };
+        let mut writer = hound::WavWriter::new(BufWriter::new(file), spec)?;
+
+        let frames = (self.capture_seconds * SAMPLE_RATE as f32) as usize;
+        let start = self.history.len().saturating_sub(frames);
+        for frame in self.history.range(start..) {
+            writer.write_sample(frame[0])?;
+            writer.write_sample(frame[1])?;
+        }
+        writer.finalize()?;
+
+        // A gap at the first captured frame lies before the capture, not inside it
+        let first = self.received - (self.history.len() - start);
+        let dropped = self.drops.iter()
+            .filter(|&&(position, _)| position > first)
+            .map(|&(_, frames)| frames)
+            .sum();
+        Ok((path.display().to_string(), dropped))
+    }
+
+    pub fn render(&mut self, ui: &mut Ui) {
+        ui.heading("Capture");
+        egui::Grid::new("CaptureOptions")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Length:");
+                ui.add(
+                    egui::Slider::new(&mut self.capture_seconds, 0.5..=MAX_CAPTURE_SECONDS as f32)
+                        .custom_formatter(|s, _| format!("{:.1} s", s))
+                        .custom_parser(parse_seconds)
+                );
+
+                ui.end_row();
+
+                if ui.button("Capture Last").clicked() {
+                    self.status = match self.dump() {
+                        Ok((path, dropped)) if dropped > 0 => format!("Saved {} ({} frames dropped)", path, dropped),
+                        Ok((path, _)) => format!("Saved {}", path),
+                        Err(err) => format!("Error: {}", err)
+                    };
+                }
+                ui.label(&self.status);
+
+                ui.end_row();
+            });
+    }
+}
@@ -0,0 +1,112 @@
+use crate::{BUFFER_SIZE, Context, Module};
+use crate::combine::Chain;
+use crate::examples::{Gain, Through};
+
+
+// Object-safe view of a Module so differently-typed modules can share a menu
+trait DynModule<const IN: usize, const OUT: usize>: Send {
+    fn map_inputs(&mut self, input_buffer: &[f32; IN]);
+    fn map_outputs(&mut self, output_buffer: &mut [f32; OUT]);
+    fn debug_taps(&self) -> &[f32];
+    fn debug_tap_names(&self) -> &[&'static str];
+}
+
+impl<M, const IN: usize, const OUT: usize> DynModule<IN, OUT> for M
+where
+    M: Module<IN, OUT>
+{
+    fn map_inputs(&mut self, input_buffer: &[f32; IN]) {
+        Module::map_inputs(self, input_buffer);
+    }
+
+    fn map_outputs(&mut self, output_buffer: &mut [f32; OUT]) {
+        Module::map_outputs(self, output_buffer);
+    }
+
+    fn debug_taps(&self) -> &[f32] {
+        Module::debug_taps(self)
+    }
+
+    fn debug_tap_names(&self) -> &[&'static str] {
+        Module::debug_tap_names(self)
+    }
+}
+
+
+pub struct BoxedModule<const IN: usize, const OUT: usize> {
+    module: Box<dyn DynModule<IN, OUT>>
+}
+
+impl<const IN: usize, const OUT: usize> BoxedModule<IN, OUT> {
+    pub fn new<M>(module: M) -> Self
+    where
+        M: Module<IN, OUT>
+    {
+        BoxedModule {
+            module: Box::new(module)
+        }
+    }
+}
+
+impl<const IN: usize, const OUT: usize> Module<IN, OUT> for BoxedModule<IN, OUT> {
+    fn map_inputs(&mut self, input_buffer: &[f32; IN]) {
+        self.module.map_inputs(input_buffer);
+    }
+
+    fn map_outputs(&mut self, output_buffer: &mut [f32; OUT]) {
+        self.module.map_outputs(output_buffer);
+    }
+
+    fn debug_taps(&self) -> &[f32] {
+        self.module.debug_taps()
+    }
+
+    fn debug_tap_names(&self) -> &[&'static str] {
+        self.module.debug_tap_names()
+    }
+}
+
+
+pub type Constructor<const IN: usize, const OUT: usize> = fn() -> BoxedModule<IN, OUT>;
+
+pub struct Registry<const IN: usize, const OUT: usize> {
+    pub(crate) entries: Vec<(&'static str, Constructor<IN, OUT>)>
+}
+
+impl<const IN: usize, const OUT: usize> Registry<IN, OUT> {
+    pub fn new() -> Self {
+        Registry {
+            entries: Vec::new()
+        }
+    }
+
+    pub fn register(mut self, name: &'static str, constructor: Constructor<IN, OUT>) -> Self {
+        self.entries.push((name, constructor));
+        self
+    }
+
+    pub fn run(self) -> eframe::Result {
+        assert!(!self.entries.is_empty());
+
+        let context: Context<IN, OUT, BUFFER_SIZE> = Context::from_registry(self);
+
+        context.run()
+    }
+}
+
+impl<const IN: usize, const OUT: usize> Default for Registry<IN, OUT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Registry<N, N> {
+    pub fn with_examples() -> Self {
+        Registry::new()
+            .register("Through", || BoxedModule::new(Through::<N>::new()))
+            .register("Gain", || BoxedModule::new(Gain::<N>::new(0.5)))
+            .register("Chain", || BoxedModule::new(
+                Chain::<_, _, N>::new(Through::<N>::new(), Gain::<N>::new(0.5))
+            ))
+    }
+}
@@ -0,0 +1,99 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use egui::ColorImage;
+
+use crate::config::{create_unique, report_dir, Config};
+use crate::output::SAMPLE_RATE;
+
+
+// A snapshot of the current measurement, written as a single JSON file
+pub struct Report {
+    pub readouts: Vec<(&'static str, Option<f64>)>,
+    pub settings: Vec<(&'static str, Config)>,
+    pub spectrum: Vec<(f64, f64)>,
+    pub response: Vec<(f64, f64)>
+}
+
+impl Report {
+    pub fn to_json(&self, timestamp: u64) -> String {
+        let mut json = String::from("{\n");
+        json += &format!("  \"timestamp\": {},\n", timestamp);
+        json += &format!("  \"sample_rate\": {},\n", SAMPLE_RATE);
+
+        json += "  \"readouts\": {\n";
+        json += &self.readouts.iter()
+            .map(|(name, value)| format!(
+                "    {}: {}",
+                quote(name),
+                value.filter(|v| v.is_finite()).map_or("null".to_string(), |v| v.to_string())
+            ))
+            .collect::<Vec<_>>()
+            .join(",\n");
+        json += "\n  },\n";
+
+        for (section, config) in &self.settings {
+            json += &format!("  {}: {{\n", quote(section));
+            json += &config.entries()
+                .map(|(key, value)| format!("    {}: {}", quote(key), quote(value)))
+                .collect::<Vec<_>>()
+                .join(",\n");
+            json += "\n  },\n";
+        }
+
+        json += &format!("  \"spectrum\": {},\n", points_json(&self.spectrum));
+        json += &format!("  \"response\": {}\n", points_json(&self.response));
+        json += "}\n";
+        json
+    }
+
+    // Written under a fresh name in the report directory; the screenshot takes the same stem
+    pub fn save(&self) -> io::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|t| t.as_secs())
+            .unwrap_or(0);
+        let (path, mut file) = create_unique(&report_dir()?, "report", "json")?;
+        file.write_all(self.to_json(timestamp).as_bytes())?;
+        Ok(path)
+    }
+}
+
+// Writes the plot screenshot next to the report, as 8-bit RGBA; an existing file is never replaced
+pub fn save_png(path: &Path, image: &ColorImage) -> io::Result<()> {
+    let writer = BufWriter::new(File::create_new(path)?);
+    let mut encoder = png::Encoder::new(writer, image.size[0] as u32, image.size[1] as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()
+        .and_then(|mut writer| writer.write_image_data(image.as_raw()))
+        .map_err(io::Error::other)
+}
+
+// (Hz, dB) pairs
+fn points_json(points: &[(f64, f64)]) -> String {
+    if points.is_empty() {
+        return "[]".to_string();
+    }
+    let rows = points.iter()
+        .map(|(hz, db)| format!("    [{:.3}, {:.2}]", hz, db))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    format!("[\n{}\n  ]", rows)
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            c if c.is_control() => quoted += &format!("\\u{:04x}", c as u32),
+            c => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    quoted
+}